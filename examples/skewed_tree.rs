@@ -0,0 +1,82 @@
+//! Generates a skewed directory tree to benchmark scanning
+//!
+//! Most of the files live under a single top level directory,
+//! the shape that used to pin one thread while the rest sat idle.
+//!
+//! Usage:
+//!
+//! ```console
+//! cargo run --release --example skewed_tree -- <dir> [files]
+//! ```
+//!
+//! Then compare the scan times with different amounts of threads:
+//!
+//! ```console
+//! seek skewed -p <dir> --threads 1 -l --output-file /dev/null
+//! seek skewed -p <dir> -l --output-file /dev/null
+//! ```
+use std::env;
+use std::fs;
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Share of the files that end up under the huge directory
+const SKEW: f64 = 0.9;
+/// Files per directory
+const FILES_PER_DIR: usize = 50;
+/// Subdirectories per directory within the huge directory
+const FAN_OUT: usize = 8;
+/// Amount of small top level directories
+const SMALL_DIRS: usize = 32;
+
+/// Fills `dir` with `files` files, spread across nested subdirectories
+fn fill(dir: &Path, files: usize) -> Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let here = files.min(FILES_PER_DIR);
+    for i in 0..here {
+        fs::write(dir.join(format!("file_{}.txt", i)), "")?;
+    }
+
+    let rest = files - here;
+    if rest == 0 {
+        return Ok(());
+    }
+
+    let per_child = rest.div_ceil(FAN_OUT);
+    let mut left = rest;
+    for i in 0..FAN_OUT {
+        if left == 0 {
+            break;
+        }
+        let amount = per_child.min(left);
+        fill(&dir.join(format!("dir_{}", i)), amount)?;
+        left -= amount;
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let root: PathBuf = match args.next() {
+        Some(root) => PathBuf::from(root),
+        None => {
+            eprintln!("usage: skewed_tree <dir> [files]");
+            std::process::exit(1);
+        }
+    };
+    let files: usize = args.next().and_then(|n| n.parse().ok()).unwrap_or(200_000);
+
+    let huge = (files as f64 * SKEW) as usize;
+    fill(&root.join("huge"), huge)?;
+
+    let per_small = (files - huge) / SMALL_DIRS;
+    for i in 0..SMALL_DIRS {
+        fill(&root.join(format!("small_{}", i)), per_small)?;
+    }
+
+    println!("Generated {} files under `{}`", files, root.display());
+    Ok(())
+}
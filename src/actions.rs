@@ -0,0 +1,285 @@
+//! Definition of `Action`
+//!
+//! What is done with the selected paths, chosen from a menu by a single
+//! key: copying them, their parent directories or their contents,
+//! opening them or their parent directories, printing a `cd` command,
+//! moving them to the trash, or running a command saved in the config.
+
+// Importing from internal modules
+use crate::config::SavedCommand;
+use crate::output::Record;
+use crate::seek::FileKind;
+use crate::utils;
+use crate::utils::Target;
+
+// Importing from external modules
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+// Using the standard library
+use std::ffi::OsString;
+use std::fs;
+use std::io::Result;
+use std::io::{self, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Leaves the menu, along with `Esc`
+const QUIT_KEY: char = 'q';
+
+/// Something done with the selected paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    CopyPath,
+    CopyParent,
+    CopyContents,
+    Open,
+    OpenParent,
+    /// Prints a command changing into the directory of the first path
+    PrintCd,
+    Command(SavedCommand),
+    Trash,
+}
+
+/// An action along with the key choosing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuEntry {
+    pub key: char,
+    pub name: String,
+    pub action: Action,
+}
+
+/// The built-in actions followed by the saved commands, the first
+/// one is chosen by `Enter`. Saved commands can't take the key of
+/// another action
+pub fn menu(saved: &[SavedCommand]) -> Result<Vec<MenuEntry>> {
+    let builtin = [
+        ('c', "Copy the path", Action::CopyPath),
+        ('p', "Copy the parent directory", Action::CopyParent),
+        ('y', "Copy the contents", Action::CopyContents),
+        ('o', "Open", Action::Open),
+        ('f', "Open the parent directory", Action::OpenParent),
+        ('d', "Print a `cd` command", Action::PrintCd),
+        ('t', "Move to the trash", Action::Trash),
+    ];
+    let mut entries: Vec<MenuEntry> = builtin
+        .into_iter()
+        .map(|(key, name, action)| MenuEntry {
+            key,
+            name: name.to_string(),
+            action,
+        })
+        .collect();
+
+    for command in saved {
+        let taken: Option<&str> = match command.key {
+            QUIT_KEY => Some("quitting"),
+            key => entries.iter().find(|entry| entry.key == key).map(|entry| entry.name.as_str()),
+        };
+        if let Some(taken) = taken {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid action `{}`: the key `{}` is already taken by `{}`",
+                    command.name, command.key, taken
+                ),
+            ));
+        }
+
+        entries.push(MenuEntry {
+            key: command.key,
+            name: command.name.clone(),
+            action: Action::Command(command.clone()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Disables raw mode when dropped
+struct RawMode;
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Waits for a single key press
+fn read_key() -> Result<KeyEvent> {
+    terminal::enable_raw_mode()?;
+    let _raw_mode = RawMode;
+
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Release {
+                return Ok(key);
+            }
+        }
+    }
+}
+
+/// Lists the menu and waits for the key of an action,
+/// `None` if the user leaves the menu instead
+pub fn choose(entries: &[MenuEntry], paths: &[String]) -> Result<Option<Action>> {
+    println!("\nWhat to do with {}?", describe(paths));
+    for entry in entries {
+        println!("  [{}] {}", entry.key, entry.name);
+    }
+    println!("  [{}] Quit", QUIT_KEY);
+    io::stdout().flush()?;
+
+    loop {
+        let key: KeyEvent = read_key()?;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char(QUIT_KEY) => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Enter => return Ok(entries.first().map(|entry| entry.action.clone())),
+            KeyCode::Char(c) => {
+                if let Some(entry) = entries.iter().find(|entry| entry.key == c) {
+                    return Ok(Some(entry.action.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The path itself when there is a single one, otherwise their amount
+fn describe(paths: &[String]) -> String {
+    match paths {
+        [path] => format!("`{}`", path),
+        paths => format!("the {} selected paths", paths.len()),
+    }
+}
+
+/// The parent directories of the paths, without repeating them
+fn parents(paths: &[&Path]) -> Vec<PathBuf> {
+    let mut parents: Vec<PathBuf> = Vec::new();
+    for parent in paths.iter().map(|path| utils::parent_dir(path)) {
+        if !parents.contains(&parent) {
+            parents.push(parent);
+        }
+    }
+    parents
+}
+
+/// Runs the command once per record, or once with every record when batched.
+/// `root` is the starting path `{root}` is relative to
+pub fn run_command(cmd: &str, batch: bool, selected: &[&Record], root: &Path) -> Result<()> {
+    let targets: Vec<Target> = selected
+        .iter()
+        .map(|record| Target {
+            path: &record.raw_path,
+            line: record.line,
+        })
+        .collect();
+
+    if batch {
+        return utils::run_cmd(utils::interpolate(cmd, &targets, root));
+    }
+    for target in targets {
+        utils::run_cmd(utils::interpolate(cmd, &[target], root))?;
+    }
+    Ok(())
+}
+
+/// Copies the value onto the clipboard, saying what was copied
+fn copy(value: String, amount: usize, what: &str) {
+    utils::copy(value);
+    match amount {
+        1 => println!("Copied {} onto the clipboard", what),
+        amount => println!("Copied {} {}s onto the clipboard", amount, what),
+    }
+}
+
+/// Does the action with every selected path, `root` being the starting path.
+/// The paths as they are on disk are acted on, the lossy ones are only shown
+pub fn run(action: &Action, selected: &[&Record], root: &Path) -> Result<()> {
+    let paths: Vec<String> = selected.iter().map(|record| record.path.clone()).collect();
+    let raw_paths: Vec<&Path> = selected.iter().map(|record| record.raw_path.as_path()).collect();
+
+    match action {
+        Action::CopyPath => copy(paths.join("\n"), paths.len(), "path"),
+        Action::CopyParent => {
+            let parents: Vec<String> = parents(&raw_paths)
+                .iter()
+                .map(|parent| parent.display().to_string())
+                .collect();
+            copy(parents.join("\n"), parents.len(), "parent directory path");
+        }
+        Action::CopyContents => {
+            let mut contents: String = String::new();
+            for record in selected {
+                if record.kind == FileKind::Dir {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Cannot copy the contents of the directory `{}`", record.path),
+                    ));
+                }
+                contents.push_str(&fs::read_to_string(&record.raw_path)?);
+            }
+            copy(contents, paths.len(), "file content");
+        }
+        Action::Open => {
+            for path in raw_paths {
+                utils::open_file(path)?;
+            }
+        }
+        Action::OpenParent => {
+            for parent in parents(&raw_paths) {
+                utils::open_file(&parent)?;
+            }
+        }
+        Action::PrintCd => {
+            let record = selected[0];
+            let dir: PathBuf = match record.kind {
+                FileKind::Dir => record.raw_path.clone(),
+                _ => utils::parent_dir(&record.raw_path),
+            };
+            let mut cd: OsString = OsString::from("cd ");
+            cd.push(utils::quote(dir.as_os_str()));
+            cd.push("\n");
+            io::stdout().write_all(&utils::os_bytes(&cd))?;
+        }
+        Action::Command(saved) => run_command(&saved.cmd, saved.batch, selected, root)?,
+        Action::Trash => {
+            print!("Move {} to the trash? [y/N] ", describe(&paths));
+            io::stdout().flush()?;
+            let confirmed: bool = read_key()?.code == KeyCode::Char('y');
+            println!();
+
+            if confirmed {
+                trash::delete_all(&raw_paths).map_err(|error| Error::other(error.to_string()))?;
+                println!("Moved {} to the trash", describe(&paths));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(name: &str, key: char) -> SavedCommand {
+        SavedCommand {
+            name: name.to_string(),
+            key,
+            cmd: "vim {}".to_string(),
+            batch: false,
+        }
+    }
+
+    #[test]
+    fn t_menu() {
+        let entries = menu(&[saved("Edit", 'e')]).unwrap();
+        assert_eq!(entries[0].action, Action::CopyPath);
+        assert_eq!(entries.last().unwrap().action, Action::Command(saved("Edit", 'e')));
+
+        let error = menu(&[saved("Edit", 'c')]).unwrap_err().to_string();
+        assert_eq!(error, "Invalid action `Edit`: the key `c` is already taken by `Copy the path`");
+        assert!(menu(&[saved("Edit", 'q')]).is_err());
+
+        let paths = ["./a/b.rs", "./a/c.rs", "d.rs"].map(Path::new);
+        assert_eq!(parents(&paths), ["./a", "."].map(PathBuf::from));
+    }
+}
@@ -0,0 +1,338 @@
+//! The compact binary cache format
+//!
+//! Layout of the file:
+//!
+//! ```text
+//! magic    b"SEEK"
+//! version  u16, little endian
+//! flags    u8, bit 0 raised when the body is zstd compressed
+//! body
+//!     header   varint length + JSON of every `Data` field except the entries
+//!     count    varint amount of entries
+//!     entries  for every entry, sorted by path:
+//!         shared   varint length of the prefix shared with the previous path
+//!         suffix   varint length + bytes of the rest of the path
+//!         kind     u8
+//!         size     varint
+//!         mtime    zigzag varint
+//!         nsec     varint nanoseconds of the mtime
+//!         mode     varint
+//! ```
+//!
+//! Paths are front coded, since sorted paths share most of their prefix
+//! with the path before them, only the differing suffix is written.
+use crate::cache::Data;
+use crate::seek::Entry;
+use crate::seek::FileKind;
+
+use serde_json::Value;
+
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+/// Identifies a binary cache file
+pub const MAGIC: &[u8; 4] = b"SEEK";
+/// Bumped whenever the layout changes
+pub const VERSION: u16 = 2;
+/// Raised when the body is zstd compressed
+const COMPRESSED: u8 = 1;
+/// The zstd compression level
+const LEVEL: i32 = 3;
+
+/// Whether the content looks like a binary cache
+pub fn is_binary(content: &[u8]) -> bool {
+    content.starts_with(MAGIC)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid binary cache: {}", message))
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &PathBuf) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_to_bytes(path: &PathBuf) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn kind_to_byte(kind: FileKind) -> u8 {
+    match kind {
+        FileKind::File => 0,
+        FileKind::Dir => 1,
+        FileKind::Symlink => 2,
+        FileKind::Other => 3,
+    }
+}
+
+fn byte_to_kind(byte: u8) -> Result<FileKind> {
+    match byte {
+        0 => Ok(FileKind::File),
+        1 => Ok(FileKind::Dir),
+        2 => Ok(FileKind::Symlink),
+        3 => Ok(FileKind::Other),
+        _ => Err(invalid("unknown entry kind")),
+    }
+}
+
+/// Writes an unsigned LEB128 varint
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Maps signed integers onto unsigned ones keeping small magnitudes small
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// A cursor over the bytes being decoded
+struct Reader<'r> {
+    bytes: &'r [u8],
+    position: usize,
+}
+
+impl<'r> Reader<'r> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, amount: usize) -> Result<&'r [u8]> {
+        let end = self
+            .position
+            .checked_add(amount)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint is too long"))
+    }
+
+    fn length(&mut self) -> Result<usize> {
+        usize::try_from(self.varint()?).map_err(|_| invalid("length out of range"))
+    }
+}
+
+/// Encodes the data into the binary format
+pub fn encode(data: &Data, compress: bool) -> Result<Vec<u8>> {
+    let mut body: Vec<u8> = Vec::new();
+
+    // every field besides the entries is kept as JSON,
+    // so new fields don't need a new layout
+    let mut header: Value = serde_json::to_value(data)?;
+    if let Some(object) = header.as_object_mut() {
+        object.remove("data");
+    }
+    let header: Vec<u8> = serde_json::to_vec(&header)?;
+    write_varint(&mut body, header.len() as u64);
+    body.extend_from_slice(&header);
+
+    let mut entries: Vec<&Entry> = data.data.iter().collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    write_varint(&mut body, entries.len() as u64);
+
+    let mut previous: Vec<u8> = Vec::new();
+    for entry in entries {
+        let path = path_to_bytes(&entry.path);
+        let shared = previous
+            .iter()
+            .zip(path.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        write_varint(&mut body, shared as u64);
+        write_varint(&mut body, (path.len() - shared) as u64);
+        body.extend_from_slice(&path[shared..]);
+        body.push(kind_to_byte(entry.kind));
+        write_varint(&mut body, entry.size);
+        write_varint(&mut body, zigzag(entry.mtime));
+        write_varint(&mut body, entry.mtime_nsec as u64);
+        write_varint(&mut body, entry.mode as u64);
+
+        previous = path;
+    }
+
+    let mut content: Vec<u8> = Vec::with_capacity(body.len() + 7);
+    content.extend_from_slice(MAGIC);
+    content.extend_from_slice(&VERSION.to_le_bytes());
+
+    if compress {
+        content.push(COMPRESSED);
+        content.extend_from_slice(&zstd::encode_all(body.as_slice(), LEVEL)?);
+    } else {
+        content.push(0);
+        content.extend_from_slice(&body);
+    }
+
+    Ok(content)
+}
+
+/// Decodes data written by `encode`
+pub fn decode(content: &[u8]) -> Result<Data> {
+    if !is_binary(content) {
+        return Err(invalid("missing magic number"));
+    }
+
+    let mut reader = Reader {
+        bytes: content,
+        position: MAGIC.len(),
+    };
+    let version = u16::from_le_bytes([reader.byte()?, reader.byte()?]);
+    if version != VERSION {
+        return Err(invalid(&format!(
+            "unsupported version {}, expected {}",
+            version, VERSION
+        )));
+    }
+    let flags = reader.byte()?;
+
+    let decompressed: Vec<u8>;
+    let body: &[u8] = if flags & COMPRESSED != 0 {
+        decompressed = zstd::decode_all(&content[reader.position..])?;
+        &decompressed
+    } else {
+        &content[reader.position..]
+    };
+
+    let mut reader = Reader {
+        bytes: body,
+        position: 0,
+    };
+
+    let header_length = reader.length()?;
+    let mut header: Value = serde_json::from_slice(reader.take(header_length)?)?;
+    match header.as_object_mut() {
+        Some(object) => object.insert("data".to_string(), Value::Array(Vec::new())),
+        None => return Err(invalid("header is not an object")),
+    };
+    let mut data: Data = serde_json::from_value(header)?;
+
+    let count = reader.length()?;
+    // not trusting the count for the allocation, a corrupted
+    // file would otherwise be able to request any amount of memory
+    let mut entries: Vec<Entry> = Vec::with_capacity(count.min(body.len()));
+    let mut previous: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        let shared = reader.length()?;
+        let suffix_length = reader.length()?;
+        if shared > previous.len() {
+            return Err(invalid("shared prefix is longer than the previous path"));
+        }
+
+        let mut path: Vec<u8> = previous[..shared].to_vec();
+        path.extend_from_slice(reader.take(suffix_length)?);
+
+        entries.push(Entry {
+            path: bytes_to_path(&path),
+            kind: byte_to_kind(reader.byte()?)?,
+            size: reader.varint()?,
+            mtime: unzigzag(reader.varint()?),
+            mtime_nsec: u32::try_from(reader.varint()?)
+                .map_err(|_| invalid("nanoseconds out of range"))?,
+            mode: u32::try_from(reader.varint()?).map_err(|_| invalid("mode out of range"))?,
+        });
+
+        previous = path;
+    }
+
+    data.data = entries;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Data {
+        let entry = |path: &str, kind: FileKind, mtime: i64| Entry {
+            path: PathBuf::from(path),
+            kind,
+            size: 1234,
+            mtime,
+            mtime_nsec: 999_999_999,
+            mode: 0o644,
+        };
+        Data::from(vec![
+            entry("/home/user/src/main.rs", FileKind::File, 1_700_000_000),
+            entry("/home/user/src", FileKind::Dir, -5),
+            entry("/home/user/src/lib.rs", FileKind::Symlink, 0),
+        ])
+    }
+
+    #[test]
+    fn t_roundtrip() {
+        for compress in [false, true] {
+            let data = sample();
+            let decoded = decode(&encode(&data, compress).unwrap()).unwrap();
+
+            let mut expected = data.data.clone();
+            expected.sort_by(|a, b| a.path.cmp(&b.path));
+            assert_eq!(decoded.data, expected);
+            assert_eq!(decoded.size, data.size);
+        }
+    }
+
+    #[test]
+    fn t_varint_zigzag() {
+        for value in [0, 1, -1, i64::MAX, i64::MIN, 1_700_000_000] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, zigzag(value));
+            let mut reader = Reader {
+                bytes: &buffer,
+                position: 0,
+            };
+            assert_eq!(unzigzag(reader.varint().unwrap()), value);
+        }
+    }
+
+    #[test]
+    fn t_rejects_corrupted() {
+        let content = encode(&sample(), false).unwrap();
+        assert!(decode(&content[..content.len() - 3]).is_err());
+        assert!(decode(b"{\"day\": 1}").is_err());
+
+        let mut future = content.clone();
+        future[4] = 99;
+        assert!(decode(&future).is_err());
+    }
+}
@@ -1,7 +1,16 @@
 //! Contains the caching definition
 //! and implementation of the program.
+//!
+//! The cache is written either in the compact binary format,
+//! see `binary`, or in JSON for debugging. The format is
+//! detected automatically when reading.
+use crate::cache::binary;
 use crate::cache::Data;
-use crate::utils;
+use crate::cache::Fingerprint;
+
+use chrono::Local;
+use clap::ValueEnum;
+use humantime::format_duration;
 
 //use serde::{Deserialize, Serialize};
 use serde_json::json; // macro to convert a hashmap into JSON
@@ -13,23 +22,67 @@ use std::io::Result;
 use std::io::{Error, ErrorKind};
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// The outcome of validating the cache
+#[derive(Debug)]
+pub enum Validity {
+    /// The cache can be used, holding its data
+    Valid(Data),
+    /// The cache matches the requested scan but is too old,
+    /// holding its data to be refreshed and the reason why
+    Expired(Data, String),
+    /// The cache can't be used, holding the reason why
+    Invalid(String),
+}
+
+/// The format the cache is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CacheFormat {
+    /// Compact, front coded binary format
+    Binary,
+    /// Prettified JSON, meant for debugging
+    Json,
+}
 
-/// A JSON Cache structure
+impl CacheFormat {
+    /// The default file name of the cache in this format
+    pub fn default_location(&self) -> &'static str {
+        match self {
+            CacheFormat::Binary => "./.info.bin",
+            CacheFormat::Json => "./.info.json",
+        }
+    }
+}
+
+/// A Cache structure
 #[derive(Clone, Debug)]
 pub struct Cache {
     location: PathBuf,
+    format: CacheFormat,
+    /// Whether the binary format is zstd compressed
+    compress: bool,
 }
 
 impl Cache {
     /// Initializes Cache. Location must include the cache file name.
     ///
-    /// Default: ./.info.json
+    /// Written in the binary format, uncompressed, unless specified otherwise
     pub fn new(location: &str) -> Self {
         Self {
             location: PathBuf::from(location),
+            format: CacheFormat::Binary,
+            compress: false,
         }
     }
 
+    /// Specifies the format to write the cache in
+    pub fn with_format(mut self, format: CacheFormat, compress: bool) -> Self {
+        self.format = format;
+        self.compress = compress;
+        self
+    }
+
     /// Returns a reference to the internal location
     pub fn location<'l>(&'l self) -> &'l Path {
         &self.location
@@ -39,45 +92,158 @@ impl Cache {
         self.location.exists()
     }
 
-    /// Creates the cache file if it doesn't exist
-    pub fn summon(&self) -> Result<()> {
-        if self.exists() {
-            return Ok(());
-        }
-        fs::File::create(&self.location)?;
-        Ok(())
-    }
-
-    /// Cache validity is defined daily
-    pub fn is_valid(&self) -> bool {
+    /// Reads the cache, checking that it was scanned with the same
+    /// parameters that are currently requested and that it's younger
+    /// than the `ttl`, otherwise it can still be refreshed
+    pub fn validate(&self, fingerprint: &Fingerprint, ttl: Duration) -> Validity {
         if !self.exists() {
-            return false;
+            return Validity::Invalid(format!(
+                "`{}` doesn't exist",
+                self.location.display()
+            ));
         }
-        // if any error is propagated, automatically returns false
-        if let Ok(content) = fs::read_to_string(&self.location) {
-            match serde_json::from_str::<Data>(&content) {
-                Ok(data) => {
-                    if data.day == utils::todays_day() {
-                        return true;
-                    }
-                    return false;
-                }
-                Err(_) => return false,
+
+        let data: Data = match self.read() {
+            Ok(data) => data,
+            Err(error) => {
+                return Validity::Invalid(format!("it could not be read ({})", error))
             }
+        };
+
+        let cached = &data.fingerprint;
+        let reason: Option<String> = if cached.root != fingerprint.root {
+            Some(format!(
+                "it was built for `{}`, not `{}`",
+                cached.root.display(),
+                fingerprint.root.display()
+            ))
+        } else if cached.cwd != fingerprint.cwd {
+            let display = |cwd: &Option<PathBuf>| match cwd {
+                Some(cwd) => cwd.display().to_string(),
+                None => "anywhere".to_string(),
+            };
+            Some(format!(
+                "it was built from `{}`, not `{}`",
+                display(&cached.cwd),
+                display(&fingerprint.cwd)
+            ))
+        } else if cached.depth != fingerprint.depth {
+            Some(format!(
+                "it was built with a depth of {}, not {}",
+                cached.depth, fingerprint.depth
+            ))
+        } else if cached.no_ignore != fingerprint.no_ignore {
+            Some(match cached.no_ignore {
+                true => "it was built disregarding ignore files".to_string(),
+                false => "it was built honoring ignore files".to_string(),
+            })
+        } else if cached.exclude != fingerprint.exclude
+            || cached.exclude_glob != fingerprint.exclude_glob
+        {
+            Some("it was built with different exclusions".to_string())
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            return Validity::Invalid(reason);
         }
-        false
+
+        let age = Duration::from_secs((Local::now().timestamp() - data.created).max(0) as u64);
+        if age > ttl {
+            return Validity::Expired(
+                data,
+                format!(
+                    "it is {} old, older than the TTL of {}",
+                    format_duration(age),
+                    format_duration(ttl)
+                ),
+            );
+        }
+
+        Validity::Valid(data)
     }
 
+    /// Reads the cache, detecting its format
     pub fn read(&self) -> Result<Data> {
-        let content = fs::read_to_string(&self.location)?;
-        return match serde_json::from_str::<Data>(&content) {
+        let content = fs::read(&self.location)?;
+        if binary::is_binary(&content) {
+            return binary::decode(&content);
+        }
+        return match serde_json::from_slice::<Data>(&content) {
             Ok(data) => Ok(data),
             Err(error) => Err(error.into()),
         };
     }
 
     pub fn write(&self, data: &Data) -> Result<()> {
-        fs::write(&self.location, data.to_string()?)?;
+        match self.format {
+            CacheFormat::Binary => fs::write(&self.location, binary::encode(data, self.compress)?)?,
+            CacheFormat::Json => fs::write(&self.location, data.to_string()?)?,
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fingerprint() -> Fingerprint {
+        Fingerprint {
+            root: PathBuf::from("b"),
+            cwd: Some(PathBuf::from("/a")),
+            depth: 3,
+            no_ignore: false,
+            exclude: vec!["target".to_string()],
+            exclude_glob: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn t_validity() {
+        let dir = tempdir().unwrap();
+        let cache = Cache::new(&dir.path().join(".info.bin").display().to_string());
+        let ttl = Duration::from_secs(60);
+
+        let reason = |validity: Validity| match validity {
+            Validity::Valid(_) => "valid".to_string(),
+            Validity::Expired(_, reason) | Validity::Invalid(reason) => reason,
+        };
+        assert!(reason(cache.validate(&fingerprint(), ttl)).contains("doesn't exist"));
+
+        let mut data = Data::from(Vec::new()).with_fingerprint(fingerprint());
+        cache.write(&data).unwrap();
+        assert!(matches!(cache.validate(&fingerprint(), ttl), Validity::Valid(_)));
+
+        // the same root spelled from another directory names other paths
+        let mut other = fingerprint();
+        other.cwd = Some(PathBuf::from("/"));
+        assert_eq!(reason(cache.validate(&other, ttl)), "it was built from `/a`, not `/`");
+        other = fingerprint();
+        other.root = PathBuf::from("a/b");
+        assert_eq!(reason(cache.validate(&other, ttl)), "it was built for `b`, not `a/b`");
+        other = fingerprint();
+        other.depth = 1;
+        assert_eq!(reason(cache.validate(&other, ttl)), "it was built with a depth of 3, not 1");
+        other = fingerprint();
+        other.no_ignore = true;
+        assert_eq!(reason(cache.validate(&other, ttl)), "it was built honoring ignore files");
+        other = fingerprint();
+        other.exclude_glob = vec!["*.log".to_string()];
+        assert_eq!(reason(cache.validate(&other, ttl)), "it was built with different exclusions");
+
+        data.created -= 3600;
+        cache.write(&data).unwrap();
+        match cache.validate(&fingerprint(), ttl) {
+            Validity::Expired(_, reason) => {
+                assert!(reason.starts_with("it is 1h") && reason.ends_with("older than the TTL of 1m"))
+            }
+            validity => panic!("{:?}", validity),
+        }
+
+        fs::write(cache.location(), b"SEEK\x02").unwrap();
+        assert!(reason(cache.validate(&fingerprint(), ttl)).starts_with("it could not be read"));
+    }
+}
@@ -1,42 +1,76 @@
 //! Definition and implementation of `Data`
 //!
 //! A wrapper to serialize the cached data.
-use crate::utils;
+use crate::seek::serialize_path;
+use crate::seek::Entry;
 
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use chrono::Local;
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::to_string_pretty;
 
 use std::io::Result;
 use std::path::PathBuf;
 
-/// Structure that defines the JSON data
-/// when reading or writing into the JSON file
+/// The parameters a scan was made with,
+/// a cache is only reused when all of them match
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// The scanned root, spelled the way it was given since
+    /// the cached paths are spelled the same way
+    #[serde(serialize_with = "serialize_path")]
+    pub root: PathBuf,
+    /// The working directory a relative root was given from,
+    /// the cached paths only resolve from there
+    #[serde(default, serialize_with = "serialize_cwd")]
+    pub cwd: Option<PathBuf>,
+    pub depth: usize,
+    /// Whether the scan disregarded ignore files
+    pub no_ignore: bool,
+    /// The `--exclude` names, sorted
+    pub exclude: Vec<String>,
+    /// The `--exclude-glob` patterns, sorted
+    pub exclude_glob: Vec<String>,
+}
+
+/// Like `serialize_path`, for the optional working directory
+fn serialize_cwd<S: Serializer>(cwd: &Option<PathBuf>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match cwd {
+        Some(cwd) => serialize_path(cwd, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Structure that defines the cached data
+/// when reading or writing into the cache file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Data {
-    pub day: u8,
+    /// When the scan was made, in seconds since the unix epoch
+    pub created: i64,
+    pub fingerprint: Fingerprint,
     pub size: usize,
-    pub data: Vec<PathBuf>,
+    /// Every scanned entry along with its metadata
+    pub data: Vec<Entry>,
 }
 
 impl Data {
-    /// It's a problem writing a vector of PathBuf's,
-    /// this function turnes this structure into a serde_json Value
-    /// making it better to write into a file
+    /// Turns this structure into a prettified JSON string,
+    /// paths that aren't valid UTF-8 are written lossily
     pub fn to_string(&self) -> Result<String> {
-        let json = json!({
-            "day": self.day,
-            "size": self.size,
-            "data": self.data.iter().map(|p| p.display().to_string()).collect::<Vec<String>>(),
-        });
-        Ok(to_string_pretty(&json)?)
+        Ok(to_string_pretty(self)?)
+    }
+
+    /// Records the parameters the data was scanned with
+    pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprint = fingerprint;
+        self
     }
 }
 
-impl From<Vec<PathBuf>> for Data {
-    fn from(data: Vec<PathBuf>) -> Self {
+impl From<Vec<Entry>> for Data {
+    fn from(data: Vec<Entry>) -> Self {
         Self {
-            day: utils::todays_day(),
+            created: Local::now().timestamp(),
+            fingerprint: Fingerprint::default(),
             size: data.len(),
             data,
         }
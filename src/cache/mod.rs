@@ -1,5 +1,9 @@
+mod binary;
 mod cache;
 mod data;
 
 pub use cache::Cache;
+pub use cache::CacheFormat;
+pub use cache::Validity;
 pub use data::Data;
+pub use data::Fingerprint;
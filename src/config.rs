@@ -0,0 +1,106 @@
+//! Contains the `Config` definition and implementation
+//!
+//! Settings read from `config.toml` within the `seek` directory of the
+//! user's configuration directory, such as `~/.config/seek/config.toml`
+//! on Linux, or from the file named by the `SEEK_CONFIG` variable:
+//!
+//! ```toml
+//! [types]
+//! notes = ["md", "org", "txt"]
+//!
+//! [[actions]]
+//! name = "Edit"
+//! key = "e"
+//! cmd = "vim +{line} {}"
+//! ```
+//!
+//! A missing file is the same as an empty one, unless `SEEK_CONFIG` names it.
+
+// Importing from external modules
+use serde::Deserialize;
+
+// Using the standard library
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// The user's settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Named sets of extensions for `--type`, overriding the built-in ones
+    pub types: HashMap<String, Vec<String>>,
+    /// Commands offered by the action menu along with the built-in actions
+    pub actions: Vec<SavedCommand>,
+}
+
+/// A command of the action menu, interpolated like `--cmd`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedCommand {
+    pub name: String,
+    /// The key choosing the command within the menu
+    pub key: char,
+    pub cmd: String,
+    /// Runs the command once with every selected path, like `--batch`
+    #[serde(default)]
+    pub batch: bool,
+}
+
+impl Config {
+    /// Where the config is read from, if there is a configuration directory
+    pub fn location() -> Option<PathBuf> {
+        match env::var_os("SEEK_CONFIG") {
+            Some(location) => Some(PathBuf::from(location)),
+            None => dirs::config_dir().map(|dir| dir.join("seek").join("config.toml")),
+        }
+    }
+
+    /// Reads the config, the defaults when there is none
+    pub fn load() -> Result<Self> {
+        match Self::location() {
+            Some(location) => Self::read(&location, env::var_os("SEEK_CONFIG").is_some()),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reads the config at the location, the defaults when it's missing
+    /// unless it was named `explicitly`
+    fn read(location: &Path, explicitly: bool) -> Result<Self> {
+        if !explicitly && !location.exists() {
+            return Ok(Self::default());
+        }
+
+        let invalid = |kind: ErrorKind, reason: String| {
+            Error::new(kind, format!("Invalid config `{}`: {}", location.display(), reason))
+        };
+        let content: String =
+            fs::read_to_string(location).map_err(|error| invalid(error.kind(), error.to_string()))?;
+        toml::from_str(&content).map_err(|error| invalid(ErrorKind::InvalidData, error.message().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn t_read() {
+        let dir = tempdir().unwrap();
+        let location = dir.path().join("config.toml");
+
+        assert!(Config::read(&location, false).unwrap().actions.is_empty());
+        let error = Config::read(&location, true).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(error.to_string().starts_with("Invalid config `"));
+
+        fs::write(&location, "[types]\nnotes = [\"md\"]\n").unwrap();
+        assert_eq!(Config::read(&location, false).unwrap().types["notes"], ["md"]);
+        fs::write(&location, "colors = true\n").unwrap();
+        assert_eq!(Config::read(&location, false).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
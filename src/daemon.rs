@@ -0,0 +1,303 @@
+//! Contains the `serve()` and `request()` definitions and implementations
+//!
+//! `seek daemon` keeps the index of the scanned root in memory, in sync
+//! with the filesystem like `seek watch`, and answers queries over a Unix
+//! domain socket so searches skip reading and parsing the cache.
+//!
+//! The protocol is a single line of JSON per message, a `Request`
+//! answered by a `Response`:
+//!
+//! ```text
+//! -> {"fingerprint": {...}, "query": {"pattern": "main", ...}}
+//! <- {"matches": [{"path": "./src/main.rs", ...}]}
+//! <- {"error": "..."}
+//! ```
+//!
+//! A request is only answered when the daemon scanned with the same
+//! parameters, see `Fingerprint`, otherwise the client falls back to
+//! scanning or reading the cache on its own. The matches are spelled like
+//! the root, so a relative root is only served to clients running from
+//! the directory the daemon was started from.
+
+// Importing from internal modules
+use crate::cache::Cache;
+use crate::cache::Data;
+use crate::cache::Fingerprint;
+use crate::seek::Entry;
+use crate::seek::Matcher;
+use crate::seek::Query;
+use crate::seek::ScanOptions;
+use crate::watch;
+use crate::watch::Index;
+
+// Importing from external modules
+use log::warn;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+// Using the standard library
+use std::fs;
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// A query for the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    /// The parameters the client would scan with
+    pub fingerprint: Fingerprint,
+    pub query: Query,
+}
+
+/// The answer of the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Response {
+    Matches(Vec<Entry>),
+    Error(String),
+}
+
+/// Answers a single request from the index
+#[cfg(unix)]
+fn answer(request: Request, index: &RwLock<Index>, fingerprint: &Fingerprint) -> Response {
+    if request.fingerprint.root == fingerprint.root && request.fingerprint.cwd != fingerprint.cwd {
+        return Response::Error(format!(
+            "the daemon serves `{}` relative to another directory",
+            fingerprint.root.display()
+        ));
+    }
+    if request.fingerprint != *fingerprint {
+        return Response::Error(format!(
+            "the daemon serves a different scan of `{}`",
+            fingerprint.root.display()
+        ));
+    }
+
+    let index = watch::read(index);
+    let matcher = match Matcher::from_query(&request.query) {
+        Ok(matcher) => matcher.with_root(index.root()),
+        Err(error) => return Response::Error(error.to_string()),
+    };
+
+    let matches: Vec<Entry> = index
+        .entries()
+        .filter(|entry| matcher.is_match(entry))
+        .cloned()
+        .collect();
+    let mut scored = matcher.scored(matches);
+    matcher.rank(&mut scored);
+    Response::Matches(scored.into_iter().map(|(entry, _)| entry).collect())
+}
+
+/// Answers every request of a connection until it's closed
+#[cfg(unix)]
+async fn handle(stream: UnixStream, index: Arc<RwLock<Index>>, fingerprint: Arc<Fingerprint>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => answer(request, &index, &fingerprint),
+            Err(error) => Response::Error(format!("Invalid request: {}", error)),
+        };
+
+        let mut response: Vec<u8> = serde_json::to_vec(&response)?;
+        response.push(b'\n');
+        writer.write_all(&response).await?;
+    }
+
+    Ok(())
+}
+
+/// Binds the socket, unless another daemon is listening on it
+#[cfg(unix)]
+async fn bind(socket: &Path) -> Result<UnixListener> {
+    if socket.exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(Error::new(
+                ErrorKind::AddrInUse,
+                format!("A daemon is already listening on `{}`", socket.display()),
+            ));
+        }
+        // left behind by a daemon that didn't exit cleanly
+        fs::remove_file(socket)?;
+    }
+    UnixListener::bind(socket)
+}
+
+/// Removes the socket once dropped, however serving ended
+#[cfg(unix)]
+struct Bound<'s> {
+    socket: &'s Path,
+}
+
+#[cfg(unix)]
+impl Drop for Bound<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.socket);
+    }
+}
+
+/// Serves queries over the socket until interrupted, keeping
+/// the index in sync with the root meanwhile, see `watch`.
+/// The data must be up to date with the root
+#[cfg(unix)]
+pub async fn serve(
+    socket: &Path,
+    root: &Path,
+    options: &ScanOptions,
+    cache: &Cache,
+    data: Data,
+    interval: Duration,
+) -> Result<()> {
+    let listener = bind(socket).await?;
+    let _bound = Bound { socket };
+    let fingerprint = Arc::new(data.fingerprint.clone());
+    let index = Arc::new(RwLock::new(Index::new(root, options, data.data)));
+    println!("Listening on `{}`", socket.display());
+
+    let server = {
+        let index = index.clone();
+        let fingerprint = fingerprint.clone();
+        let log = options.log;
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(error) => {
+                        if log {
+                            warn!("{}", error);
+                        }
+                        continue;
+                    }
+                };
+
+                let index = index.clone();
+                let fingerprint = fingerprint.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = handle(stream, index, fingerprint).await {
+                        if log {
+                            warn!("{}", error);
+                        }
+                    }
+                });
+            }
+        })
+    };
+
+    let result = watch::keep_in_sync(root, options, cache, &index, &fingerprint, interval).await;
+    server.abort();
+    result
+}
+
+/// Sends the request to the daemon listening on the socket, returning its matches
+#[cfg(unix)]
+pub async fn request(socket: &Path, request: &Request) -> Result<Vec<Entry>> {
+    let stream = UnixStream::connect(socket).await?;
+    let (reader, mut writer) = stream.into_split();
+
+    let mut message: Vec<u8> = serde_json::to_vec(request)?;
+    message.push(b'\n');
+    writer.write_all(&message).await?;
+
+    let line = match BufReader::new(reader).lines().next_line().await? {
+        Some(line) => line,
+        None => {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "the daemon closed the connection",
+            ))
+        }
+    };
+
+    match serde_json::from_str::<Response>(&line)? {
+        Response::Matches(matches) => Ok(matches),
+        Response::Error(error) => Err(Error::new(ErrorKind::Other, error)),
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve(
+    _socket: &Path,
+    _root: &Path,
+    _options: &ScanOptions,
+    _cache: &Cache,
+    _data: Data,
+    _interval: Duration,
+) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "The daemon relies on Unix domain sockets, which are not supported on this OS.",
+    ))
+}
+
+#[cfg(not(unix))]
+pub async fn request(_socket: &Path, _request: &Request) -> Result<Vec<Entry>> {
+    Err(Error::new(ErrorKind::Unsupported, "OS not supported."))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::seek::Exclusions;
+    use crate::seek::FileKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn t_answer() {
+        let options = ScanOptions {
+            depth: usize::MAX,
+            log: false,
+            no_ignore: true,
+            threads: 1,
+            exclusions: Exclusions::new(&[], &[]).unwrap(),
+        };
+        let entry = |path: &str| Entry {
+            path: PathBuf::from(path),
+            kind: FileKind::File,
+            size: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            mode: 0,
+        };
+        let root = Path::new("./missing");
+        let index = RwLock::new(Index::new(
+            root,
+            &options,
+            vec![entry("./missing/main.rs"), entry("./missing/lib.rs")],
+        ));
+        let fingerprint = Fingerprint {
+            root: PathBuf::from("./missing"),
+            cwd: Some(PathBuf::from("/home")),
+            ..Fingerprint::default()
+        };
+        let mut request = Request {
+            fingerprint: fingerprint.clone(),
+            query: Query {
+                pattern: "main".to_string(),
+                ..Query::default()
+            },
+        };
+
+        match answer(request.clone(), &index, &fingerprint) {
+            Response::Matches(matches) => assert_eq!(matches, vec![entry("./missing/main.rs")]),
+            Response::Error(error) => panic!("{}", error),
+        }
+
+        // the matches would not resolve from another directory
+        request.fingerprint.cwd = Some(PathBuf::from("/"));
+        match answer(request.clone(), &index, &fingerprint) {
+            Response::Error(error) => assert!(error.contains("relative to another directory")),
+            Response::Matches(_) => panic!("answered a client running elsewhere"),
+        }
+
+        request.fingerprint = fingerprint.clone();
+        request.fingerprint.depth = 1;
+        assert!(matches!(answer(request, &index, &fingerprint), Response::Error(_)));
+    }
+}
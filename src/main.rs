@@ -1,23 +1,59 @@
 //! A rust binary to seek objects quickly via the terminal with caching functionality
 #![allow(warnings)]
 /// Defining modules
+mod actions;
 mod cache;
+mod config;
+mod daemon;
 mod options;
+mod output;
+mod picker;
+mod preview;
 mod regex_builder;
 mod seek;
 mod utils;
+mod watch;
 
 /// Importing from internal and external libraries and modules
+use actions::Action;
+use actions::MenuEntry;
 use cache::Cache;
+use cache::CacheFormat;
 use cache::Data;
-use clap::Parser;
-use regex::Regex;
-use regex_builder::build_regex;
+use cache::Fingerprint;
+use cache::Validity;
+use config::Config;
+use daemon::Request;
+use clap::{Parser, Subcommand};
 use seek::filter_excluded_dirs;
-use seek::filter_included_dirs;
+use output::OutputFormat;
+use output::Printer;
+use output::Record;
+use preview::Previewer;
+use regex_builder::build_regex;
+use regex_builder::CaseMode;
+use seek::refresh;
+use seek::resolve_types;
 use seek::scan;
 use seek::search;
+use seek::Entry;
+use seek::stream;
+use seek::Exclusions;
+use seek::Matcher;
+use seek::normalize_extension;
+use seek::parse_size;
+use seek::parse_time;
+use seek::search_contents;
+use seek::sort_entries;
+use seek::ContentMatch;
+use seek::ContentSearch;
+use seek::PathMode;
+use seek::Query;
+use seek::ScanOptions;
 use seek::ScanResult;
+use seek::ScanStream;
+use seek::SizeFilter;
+use seek::SortKey;
 use log::{
     warn,
     error,
@@ -29,11 +65,13 @@ use std::collections::HashSet;
 /// Making use of the standard library
 use std::env::consts::OS;
 use std::env::current_dir;
-use std::io::Result;
+use std::io::{self, IsTerminal, Result};
+use std::path::Path;
 use std::path::PathBuf;
 use std::path::MAIN_SEPARATOR;
 use std::process::exit;
 use std::thread;
+use std::time::Duration;
 use std::time::Instant;
 use std::io::Write;
 
@@ -45,24 +83,33 @@ use std::io::Write;
 ///
 /// For further help, reference the help menu.
 #[derive(Debug, Clone, Parser)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Arguments {
-    /// The regex query to apply on the base name of paths
-    query: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The regex query to apply on the base name of paths, or a glob along with `--glob`
+    #[arg(required = true)]
+    query: Option<String>,
 
     /// The initial path to seek from [default: current working directory]
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     path: Option<String>,
 
     /// The recursion depth limit when walking directories
-    #[arg(long, default_value_t = 1_000_000)]
+    #[arg(long, default_value_t = 1_000_000, global = true)]
     depth: usize,
 
+    /// The amount of threads walking directories [default: available cores]
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
     /// Logs the state of the program to the standard output
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     log: bool,
 
     /// Signals to start seeking from the root directory
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     root: bool,
 
     /// Only seek files
@@ -81,62 +128,239 @@ struct Arguments {
     #[arg(long)]
     append: bool,
 
-    /// Used alongside `--output-file`, indicates to write the result enumerated
+    /// Used alongside `--output-file` or `--print`, indicates to write the result enumerated
     #[arg(long)]
     enumerate: bool,
 
+    /// How the matches are written, the structured formats include the path,
+    /// type, size, modification time and, when ranked, the score.
+    /// Implies `--print` unless writing into `--output-file`
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    format: OutputFormat,
+
+    /// Ends every path with a NUL byte instead of a new line, such as for
+    /// `xargs -0`. Implies `--print` unless writing into `--output-file`
+    #[arg(short = '0', long, conflicts_with = "format")]
+    print0: bool,
+
+    /// Prints the matches as soon as they are found instead of prompting to select one.
+    /// Implied when the standard output is not a terminal
+    #[arg(long)]
+    print: bool,
+
     /// Only seek symbolic links
     #[arg(short, long)]
     symlinks: bool,
 
-    /// Case sensitive regex matching
-    #[arg(long)]
+    /// Case sensitive matching, a shorthand for `--case sensitive`
+    #[arg(long, conflicts_with = "case")]
     cs: bool,
 
+    /// How letters of different case are matched, `smart` being
+    /// case sensitive only when the query contains an uppercase letter
+    #[arg(long, value_enum, default_value_t = CaseMode::Smart)]
+    case: CaseMode,
+
     /// Modifies the regex query to match
     /// the exact string literal
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "glob")]
     exact: bool,
 
+    /// Treats the query as a glob pattern instead of a regex, supporting
+    /// `*`, `?`, `[...]`, `{a,b}` and `**`. The glob must match the whole name
+    #[arg(short, long)]
+    glob: bool,
+
+    /// Fuzzy matches the query against the path relative to the starting path,
+    /// listing the best matches first
+    #[arg(short = 'z', long, conflicts_with_all = ["glob", "exact"])]
+    fuzzy: bool,
+
+    /// Treats the query as a boolean expression combining several patterns,
+    /// e.g. `name:/test/ and ext:rs and not path:target`
+    #[arg(long = "expr", conflicts_with_all = ["glob", "exact", "fuzzy", "full_path"])]
+    expression: bool,
+
+    /// Matches the query against the whole path instead of the base name,
+    /// either relative to the starting path or absolute [default: relative]
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "relative")]
+    full_path: Option<PathMode>,
+
+    /// A regex the parent directory must match, while the query still
+    /// targets the base name. Relative to the starting path unless
+    /// `--full-path absolute` is given
+    #[arg(long)]
+    path_regex: Option<String>,
+
     /// The cache location to store or read from
-    #[arg(long, default_value_t=String::from("./.info.json"))]
-    cache_location: String,
+    /// [default: ./.info.bin, or ./.info.json for the JSON format]
+    #[arg(long, global = true)]
+    cache_location: Option<String>,
+
+    /// The format to write the cache in, reading detects the format
+    #[arg(long, value_enum, default_value_t = CacheFormat::Binary, global = true)]
+    cache_format: CacheFormat,
+
+    /// Compresses the binary cache with zstd
+    #[arg(long, global = true)]
+    compress_cache: bool,
 
     /// Signals to only scan and cache without any search
     #[arg(short, long)]
     cache: bool,
 
+    /// How long the cache stays valid, such as `30min`, `12h` or `7days`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1day", global = true)]
+    cache_ttl: Duration,
+
     /// Ignores cache invalidity, searching the existing cache anyway
     #[arg(short, long)]
     ignore_update: bool,
 
-    /// Signals to update the cache regardless of its validity
+    /// Signals to update the cache regardless of its validity,
+    /// only reading the directories that changed since
     #[arg(long)]
     update_cache: bool,
 
+    /// Rebuilds the cache with a full scan instead of
+    /// only reading the directories that changed
+    #[arg(long, global = true)]
+    rebuild_cache: bool,
+
     /// Uses the cache instead of scanning directories
     #[arg(short, long)]
     use_cache: bool,
 
+    /// The Unix domain socket of the daemon
+    /// [default: the cache location followed by `.sock`]
+    #[arg(long, global = true)]
+    socket: Option<String>,
+
+    /// Never asks a running daemon, scanning or reading the cache instead
+    #[arg(long)]
+    no_daemon: bool,
+
+    /// Disregards `.gitignore`, `.ignore` and `.seekignore` files when scanning
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
     /// Specifies what parent directory name should be present within
     /// the found paths. If not present, automatically discards path
     #[arg(long)]
     include: Vec<String>,
 
+    /// Only considers names ending with one of the comma separated extensions, such as `rs,toml`
+    #[arg(long, value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// Only considers names ending with an extension of the comma separated types,
+    /// such as `rust`, `image` or `archive`. More can be defined in the config
+    #[arg(short = 't', long = "type", value_delimiter = ',')]
+    file_type: Vec<String>,
+
+    /// Only considers files of the given size: `+10M` at least, `-10M` at most,
+    /// `10M` exactly. Units are `k`, `M`, `G`, `T` or `Ki`, `Mi`, `Gi`, `Ti`
+    #[arg(long, value_parser = SizeFilter::parse, allow_hyphen_values = true)]
+    size: Vec<SizeFilter>,
+
+    /// Only considers paths modified within the duration, such as `2d`,
+    /// or since the date, such as `2026-01-01`
+    #[arg(long, visible_alias = "changed-within", value_parser = parse_time)]
+    newer: Option<i64>,
+
+    /// Only considers paths modified before the duration, such as `2d`,
+    /// or before the date, such as `2026-01-01`
+    #[arg(long, visible_alias = "changed-before", value_parser = parse_time)]
+    older: Option<i64>,
+
+    /// Only keeps the files whose contents match the regex, skipping binaries
+    #[arg(long)]
+    contains: Option<String>,
+
+    /// Used alongside `--contains`, files larger than the size are skipped, such as `10M`
+    #[arg(long, value_parser = parse_size, default_value = "50M", requires = "contains")]
+    max_filesize: u64,
+
+    /// Used alongside `--contains`, lists every matching line along with its number
+    #[arg(short = 'n', long, requires = "contains")]
+    line_number: bool,
+
+    /// Sorts the matches, every key breaks ties by the path. `none` writes the
+    /// matches out as soon as they are found, `score` sorts by path without `--fuzzy`
+    /// [default: score along with `--fuzzy`, otherwise path]
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+
+    /// Reverses the order of the matches
+    #[arg(long)]
+    reverse: bool,
+
+    /// Only lists the first N matches, the search stops early
+    /// whenever the order allows it, such as with `--sort none`
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Only lists the first match, the same as `--limit 1`
+    #[arg(long, conflicts_with = "limit")]
+    first: bool,
+
     /// Specifies what parent directory name should NOT be present
     /// within the found paths. If present, automatically discards path
-    #[arg(long, short = 'x')]
+    #[arg(long, short = 'x', global = true)]
     exclude: Vec<String>,
 
-    /// Instead of copying the selected path, the
-    /// file is ran in an attempt to open it
+    /// Glob pattern of paths to leave out of the scan, excluded directories are
+    /// never descended. Patterns without a `/` are matched against the name
+    #[arg(long, global = true)]
+    exclude_glob: Vec<String>,
+
+    /// Instead of choosing what to do from the action menu,
+    /// the selected file is ran in an attempt to open it
     #[arg(short)]
     open: bool,
 
+    /// Lists the matches with their index and reads the selected
+    /// index from the prompt, instead of the full screen picker
+    #[arg(long)]
+    simple_ui: bool,
+
+    /// Previews the highlighted match in the picker with the output of the
+    /// command instead, where `{}` is the path, such as `head -n 50 {}`
+    #[arg(long, conflicts_with = "simple_ui")]
+    preview_cmd: Option<String>,
+
     /// Interpolates the found path into the command, and runs the command.
-    /// Use `{}` as a placeholder for the path
+    /// Use `{}` as a placeholder for the path, `{/}` for its name, `{//}` for
+    /// its parent, `{.}` for the path without its extension, `{/.}` for the
+    /// name without it, `{ext}` for the extension, `{root}` for the path
+    /// relative to the starting path and `{line}` for the matching line along
+    /// with `--contains`. Every value is quoted for the shell
     #[arg(long)]
-    cmd: Option<String>
+    cmd: Option<String>,
+
+    /// Runs `--cmd` once with the values of every selected path in place of
+    /// each placeholder, separated by spaces, instead of once per selected path
+    #[arg(long, requires = "cmd")]
+    batch: bool,
+}
+
+/// Modes other than seeking
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Builds the cache, then keeps it up to date by watching the scanned
+    /// root for changes until interrupted
+    Watch {
+        /// How often the changes are written into the cache, such as `5s` or `1min`
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "5s")]
+        flush_interval: Duration,
+    },
+    /// Keeps the scanned index in memory and in sync like `watch`, answering
+    /// the queries of other invocations over a Unix domain socket
+    Daemon {
+        /// How often the changes are written into the cache, such as `5s` or `1min`
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "5s")]
+        flush_interval: Duration,
+    },
 }
 
 impl Arguments {
@@ -155,13 +379,204 @@ impl Arguments {
             .unwrap_or(".")
             .to_string()
     }
+
+    /// The parameters the cache must have been scanned with to be reused
+    fn get_fingerprint(&self, path: &Path) -> Fingerprint {
+        let mut exclude = self.exclude.clone();
+        let mut exclude_glob = self.exclude_glob.clone();
+        exclude.sort();
+        exclude_glob.sort();
+
+        Fingerprint {
+            root: path.to_path_buf(),
+            // a relative root only names the same paths from the same directory
+            cwd: match path.is_absolute() {
+                true => None,
+                false => current_dir().ok(),
+            },
+            depth: self.depth,
+            no_ignore: self.no_ignore,
+            exclude,
+            exclude_glob,
+        }
+    }
+
+    /// Validates the cache against the requested scan, a requested
+    /// update or rebuild turns a valid cache into an expired one
+    fn validate_cache(&self, cache: &Cache, path: &Path) -> Validity {
+        match cache.validate(&self.get_fingerprint(path), self.cache_ttl) {
+            Validity::Valid(data) if self.rebuild_cache => {
+                Validity::Expired(data, "a rebuild was requested".to_string())
+            }
+            Validity::Valid(data) if self.update_cache => {
+                Validity::Expired(data, "an update was requested".to_string())
+            }
+            validity => validity,
+        }
+    }
+
+    /// `--cs` being a shorthand for `--case sensitive`
+    fn get_case(&self) -> CaseMode {
+        if self.cs {
+            CaseMode::Sensitive
+        } else {
+            self.case
+        }
+    }
+
+    /// The criteria of the search, the config is only read for `--type`
+    fn get_query(&self) -> Result<Query> {
+        let mut extensions: Vec<String> = match self.file_type.is_empty() {
+            true => Vec::new(),
+            false => resolve_types(&self.file_type, &Config::load()?.types)?,
+        };
+        extensions.extend(self.ext.iter().map(|e| normalize_extension(e)));
+
+        Ok(Query {
+            // only absent along with a subcommand
+            pattern: self.query.clone().unwrap_or_default(),
+            glob: self.glob,
+            fuzzy: self.fuzzy,
+            expression: self.expression,
+            full_path: self.full_path,
+            path_regex: self.path_regex.clone(),
+            case: self.get_case(),
+            exact: self.exact,
+            dirs: self.dirs,
+            files: self.files,
+            symlinks: self.symlinks,
+            include: self.include.clone(),
+            extensions,
+            size: self.size.clone(),
+            newer: self.newer,
+            older: self.older,
+        })
+    }
+
+    /// The order of the matches, ranked along with `--fuzzy`, otherwise by path.
+    /// Only fuzzy matches are scored, the rest fall back to the path
+    fn get_sort(&self, matcher: &Matcher) -> SortKey {
+        match self.sort {
+            Some(SortKey::Score) if !matcher.is_ranked() => SortKey::Path,
+            Some(sort) => sort,
+            None if matcher.is_ranked() => SortKey::Score,
+            None => SortKey::Path,
+        }
+    }
+
+    fn get_limit(&self) -> Option<usize> {
+        if self.first {
+            Some(1)
+        } else {
+            self.limit
+        }
+    }
+
+    /// The search within files, if any
+    fn get_content_search(&self) -> Result<Option<ContentSearch>> {
+        match &self.contains {
+            Some(contains) => Ok(Some(ContentSearch::new(
+                build_regex(contains.clone(), self.get_case(), false)?,
+                self.max_filesize,
+                self.line_number,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn get_socket(&self) -> PathBuf {
+        match &self.socket {
+            Some(socket) => PathBuf::from(socket),
+            None => PathBuf::from(format!("{}.sock", self.get_cache_location())),
+        }
+    }
+
+    /// The cache left at the default location of previous versions, which
+    /// is no longer read now that the binary format is the default
+    fn get_legacy_cache(&self) -> Option<&'static str> {
+        let legacy: &'static str = CacheFormat::Json.default_location();
+        let is_default: bool = self.cache_location.is_none() && self.cache_format == CacheFormat::Binary;
+        (is_default && Path::new(legacy).exists()).then_some(legacy)
+    }
+
+    fn get_cache_location(&self) -> &str {
+        match &self.cache_location {
+            Some(location) => location,
+            None => self.cache_format.default_location(),
+        }
+    }
+}
+
+/// Updates the cache, either refreshing the directories that changed
+/// when the cache matches the requested scan, or scanning from scratch
+async fn update_cache(
+    args: &Arguments,
+    path: &Path,
+    cache: &Cache,
+    scan_options: &ScanOptions,
+    validity: Validity,
+) -> Result<Data> {
+    let start = Instant::now();
+    let result: ScanResult = match validity {
+        // the cache was scanned with the same parameters,
+        // only the directories that changed are read
+        Validity::Valid(data) if !args.rebuild_cache => {
+            if args.log {
+                info!("Refreshing directories...");
+            }
+            refresh(path, scan_options, data.data).await?
+        }
+        Validity::Expired(data, reason) if !args.rebuild_cache => {
+            if args.log {
+                warn!("Cache is invalid, {}.", reason);
+                info!("Refreshing directories...");
+            }
+            refresh(path, scan_options, data.data).await?
+        }
+        validity => {
+            if args.log {
+                if let Validity::Invalid(reason) | Validity::Expired(_, reason) = validity {
+                    warn!("Cache is invalid, {}.", reason);
+                }
+                info!("Scanning directories...");
+            }
+            scan(path, scan_options).await?
+        }
+    };
+    let end = Instant::now();
+    let data: Data = Data::from(result.entries).with_fingerprint(args.get_fingerprint(path));
+
+    // cache is now updated
+    cache.write(&data)?;
+
+    if args.log {
+        info!("Updated cache.");
+        info!("Cached into `{}`", cache.location().display());
+        print!("\n"); // new line for better visuals
+        info!("Scanned in: {:?}\n", end - start);
+        info!("Success: {}", utils::format_num(result.success_count));
+        info!("Errors: {}", utils::format_num(result.error_count));
+    }
+
+    Ok(data)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Arguments::parse();
     let path = PathBuf::from(args.get_path());
-    let cache = Cache::new(&args.cache_location);
+    let cache = Cache::new(args.get_cache_location())
+        .with_format(args.cache_format, args.compress_cache);
+    let scan_options = ScanOptions {
+        depth: args.depth,
+        log: args.log,
+        threads: match args.threads {
+            Some(threads) => threads,
+            None => thread::available_parallelism()?.into(),
+        },
+        no_ignore: args.no_ignore,
+        exclusions: Exclusions::new(&args.exclude, &args.exclude_glob)?,
+    };
 
     // initializing the pretty logger with Info level tracing
     pretty_env_logger::formatted_builder()
@@ -178,164 +593,337 @@ async fn main() -> Result<()> {
         })
         .init();
 
-    let data: Data = if args.cache || args.use_cache || args.update_cache {
+    match &args.command {
+        Some(Command::Watch { flush_interval }) => {
+            // the cache is brought up to date once, from then
+            // on the changes are applied as they are reported
+            let data: Data = update_cache(&args, &path, &cache, &scan_options, args.validate_cache(&cache, &path)).await?;
+            return watch::watch(&path, &scan_options, &cache, data, *flush_interval).await;
+        }
+        Some(Command::Daemon { flush_interval }) => {
+            let data: Data = update_cache(&args, &path, &cache, &scan_options, args.validate_cache(&cache, &path)).await?;
+            return daemon::serve(&args.get_socket(), &path, &scan_options, &cache, data, *flush_interval).await;
+        }
+        None => {}
+    }
+
+    let from_cache: bool = args.cache || args.use_cache || args.update_cache || args.rebuild_cache;
+    if let Some(legacy) = args.get_legacy_cache().filter(|_| from_cache) {
+        warn!(
+            "`{}` was written by a previous version and is no longer read, the cache now defaults to `{}`. It can be removed.",
+            legacy,
+            args.get_cache_location()
+        );
+    }
+
+    let query: Query = args.get_query()?;
+    let matcher = Matcher::from_query(&query)?.with_root(&path);
+    let content_search: Option<ContentSearch> = args.get_content_search()?;
+    let sort: SortKey = args.get_sort(&matcher);
+    let limit: Option<usize> = args.get_limit();
+    // whether the matches are kept in the order they are found, so the
+    // search can stop as soon as enough of them are found
+    let as_found: bool = sort == SortKey::None && !args.reverse;
+
+    // unless the user is prompted to select a path,
+    // matches are written out as soon as they are found
+    let mut printer: Option<Printer> = if let Some(file) = &args.output_file {
+        Some(Printer::file(file, args.append, args.enumerate)?.with_format(args.format, args.print0))
+    } else if args.print || args.print0 || args.format != OutputFormat::Plain || !io::stdout().is_terminal() {
+        Some(Printer::stdout(args.enumerate).with_format(args.format, args.print0))
+    } else {
+        None
+    };
+    // the saved commands are only read when the menu will be shown,
+    // so a broken config doesn't fail every other search
+    let menu: Vec<MenuEntry> = match printer.is_none() && args.cmd.is_none() && !args.open {
+        true => actions::menu(&Config::load()?.actions)?,
+        false => Vec::new(),
+    };
+
+    // a running daemon answers right away, unless the cache is being written
+    let socket: PathBuf = args.get_socket();
+    let from_daemon: Option<Vec<Entry>> =
+        if args.no_daemon || args.cache || args.update_cache || args.rebuild_cache || !socket.exists() {
+            None
+        } else {
+            let request = Request {
+                fingerprint: args.get_fingerprint(&path),
+                query: query.clone(),
+            };
+            match daemon::request(&socket, &request).await {
+                Ok(matches) => {
+                    if args.log {
+                        info!("Answered by the daemon on `{}`", socket.display());
+                    }
+                    Some(matches)
+                }
+                Err(error) => {
+                    if args.log {
+                        warn!("Could not ask the daemon, {}.", error);
+                    }
+                    None
+                }
+            }
+        };
+
+    let mut matches: Vec<Entry> = if let Some(matches) = from_daemon {
+        matches
+    } else if from_cache {
         // If user wants to do anything with the cache
         // obtaining the data from cache
-        cache.summon()?;
-        let data: Data = if cache.is_valid() && !args.update_cache {
-            // if cache is valid and user didn't specify to update the cache
-            if args.cache {
-                // it doesn't matter if the cache is valid,
-                // raising the --cache (-c) flag is reserved for
-                // solely caching; early exits. Otherwise, the user
-                // should be using the --update-cache flag to force an update.
-                exit(0); // user just wanted to cache
+        let validity: Validity = args.validate_cache(&cache, &path);
+
+        let data: Data = match validity {
+            Validity::Valid(data) => {
+                // if cache is valid and user didn't specify to update the cache
+                if args.cache {
+                    // it doesn't matter if the cache is valid,
+                    // raising the --cache (-c) flag is reserved for
+                    // solely caching; early exits. Otherwise, the user
+                    // should be using the --update-cache flag to force an update.
+                    exit(0); // user just wanted to cache
+                }
+                data
             }
-            cache.read()?
-        } else {
-            // if cache is invalid or user wants to force an update
-            if !args.ignore_update {
-                // If the user didn't specify to ignore the validity of the cache
+            Validity::Expired(data, reason) if args.ignore_update && !args.rebuild_cache => {
                 if args.log {
-                    warn!("Cache is invalid.");
-                    info!("Scanning directories...");
+                    warn!("Cache is invalid, {}. Using it anyway.", reason);
                 }
-
-                let start = Instant::now();
-                let result: ScanResult = scan(&path, args.depth, args.log).await?;
-                let end = Instant::now();
-                let data: Data = Data::from(result.paths);
-
-                // cache is now updated
-                cache.write(&data)?;
-
+                data
+            }
+            Validity::Invalid(reason) if args.ignore_update && !args.rebuild_cache => {
+                // the user specified to ignore the validity of the cache
                 if args.log {
-                    info!("Updated cache.");
-                    info!("Cached into `{}`", cache.location().display());
-                    print!("\n"); // new line for better visuals
-                    info!("Scanned in: {:?}\n", end - start);
-                    info!("Success: {}", utils::format_num(result.success_count));
-                    info!("Errors: {}", utils::format_num(result.error_count));
+                    warn!("Cache is invalid, {}. Using it anyway.", reason);
                 }
+                cache.read()?
+            }
+            validity => {
+                // if cache is invalid or user wants to force an update
+                let data: Data = update_cache(&args, &path, &cache, &scan_options, validity).await?;
 
                 if args.cache {
                     exit(0); // user just wanted to cache
                 }
+
+                data
             }
+        };
+
+        // Next Step: Searching data
+        if args.log {
+            info!("Matching query...");
+        }
 
-            cache.read()?
+        let start = Instant::now();
+        let matches: Vec<Entry> = match limit.filter(|_| as_found && content_search.is_none()) {
+            // only searching until enough matches are found
+            Some(limit) => data
+                .data
+                .iter()
+                .filter(|e| matcher.is_match(e) && !scan_options.exclusions.is_excluded_any(&e.path, &path))
+                .take(limit)
+                .cloned()
+                .collect(),
+            None => {
+                let matches: Vec<Entry> = search(&data.data, &matcher).await?;
+                // fresh scans already left out excluded paths while walking
+                filter_excluded_dirs(matches, &scan_options.exclusions, &path)
+            }
         };
+        let end = Instant::now();
+
+        if args.log {
+            info!("Searched in: {:?}\n", end - start);
+        }
 
-        data
+        matches
     } else {
-        // no need to touch the cache because if was not indicated
+        // no need to touch the cache because if was not indicated,
+        // matching entries while they are scanned
         if args.log {
             info!("Scanning directories...");
         }
 
         let start = Instant::now();
-        let result: ScanResult = scan(&path, args.depth, args.log).await?;
+        let stream: ScanStream = stream(&path, &scan_options)?;
+        let mut matches: Vec<Entry> = Vec::new();
+        // sorted matches can only be written once all of them are known,
+        // and contents are searched on every core once the scan is done
+        let streamed: bool = as_found && content_search.is_none();
+        let mut found: usize = 0;
+
+        for entry in stream.receiver.iter() {
+            if !matcher.is_match(&entry) {
+                continue;
+            }
+
+            if streamed && limit.is_some_and(|limit| found >= limit) {
+                // dropping the stream stops the walk
+                break;
+            }
+            found += 1;
+
+            let printer = match printer.as_mut().filter(|_| streamed) {
+                Some(printer) => printer,
+                None => {
+                    matches.push(entry);
+                    continue;
+                }
+            };
+
+            printer.print(&Record::new(&entry, None))?;
+            if printer.is_closed() {
+                // whoever was reading the output is gone
+                break;
+            }
+        }
+
+        let result: ScanResult = stream.finish()?;
         let end = Instant::now();
-        let data: Data = Data::from(result.paths);
 
         if args.log {
             print!("\n"); // a new line for better visuals
-            info!("Scanned in: {:?}\n", end - start);
+            info!("Scanned and searched in: {:?}\n", end - start);
             info!("Success: {}", utils::format_num(result.success_count));
             info!("Errors: {}", utils::format_num(result.error_count));
         }
 
-        data
+        matches
     };
 
-    // Next Step: Searching data
-    if args.log {
-        info!("Matching query...");
+    let mut matches: Vec<Entry> = matches;
+    if sort != SortKey::Score {
+        sort_entries(&mut matches, sort);
+    }
+    // scored once, the scores are listed along with the matches
+    let mut scored: Vec<(Entry, Option<i64>)> = matcher.scored(matches);
+    if sort == SortKey::Score {
+        // the best fuzzy matches first
+        matcher.rank(&mut scored);
     }
+    if args.reverse {
+        scored.reverse();
+    }
+    let (matches, scores): (Vec<Entry>, Vec<Option<i64>>) = scored.into_iter().unzip();
 
-    let query: Regex = build_regex(args.query, args.cs, args.exact)?;
+    let matches: Vec<(ContentMatch, Option<i64>)> = match &content_search {
+        Some(content_search) => {
+            if args.log {
+                info!("Searching contents...");
+            }
+            // sorted beforehand, so the search can stop once enough files matched
+            let found: Vec<ContentMatch> = search_contents(&matches, content_search, limit).await?;
+
+            // the matching files keep their order, so their scores are found along the way
+            let mut scored = matches.iter().zip(scores);
+            found
+                .into_iter()
+                .map(|found| {
+                    let score: Option<i64> = scored
+                        .find(|(entry, _)| entry.path == found.entry.path)
+                        .and_then(|(_, score)| score);
+                    (found, score)
+                })
+                .collect()
+        }
+        None => matches
+            .into_iter()
+            .zip(scores)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(entry, score)| (ContentMatch { entry, lines: Vec::new() }, score))
+            .collect(),
+    };
 
-    let start = Instant::now();
-    let mut matches: Vec<PathBuf> =
-        search(&data.data, query, args.dirs, args.files, args.symlinks).await?;
-    let end = Instant::now();
+    // what is listed, a record per matching line along with `--line-number`
+    let mut results: Vec<Record> = Vec::new();
+    for (found, score) in matches.iter() {
+        let record = Record::new(&found.entry, *score);
 
-    // filtering based on argument specifications
-    matches = filter_included_dirs(matches, &args.include);
-    matches = filter_excluded_dirs(matches, &args.exclude);
+        if !args.line_number {
+            let line: Option<usize> = found.lines.first().map(|l| l.number);
+            results.push(Record { line, ..record });
+            continue;
+        }
 
-    if matches.is_empty() {
-        print!("\n"); // just adding a new line for better visual
-        error!("No matches were found.");
-        exit(1);
+        for line in found.lines.iter() {
+            results.push(Record {
+                line: Some(line.number),
+                text: Some(line.line.clone()),
+                ..record.clone()
+            });
+        }
     }
 
-    // mapping to a different type for terminal output
-    let matches: Vec<String> = matches.iter().map(|p| p.display().to_string()).collect();
-
-    let beautified_ui: String = if !args.output_file.is_none() {
-        // if an output file was specified
-        //
-        // `args.enumerate` by default false
-        utils::pretty_interface(&matches, args.enumerate)
-    } else {
-        // no specification of an output file
-        utils::pretty_interface(&matches, true)
-    };
+    if let Some(mut printer) = printer {
+        for record in results.iter() {
+            printer.print(record)?;
+        }
 
-    // in case of wanting to save to a file instead
-    if let Some(file) = args.output_file {
-        // Reminder: args.append is a boolean flag
-        let _ = utils::write_to(file, beautified_ui, args.append);
+        if printer.finish()? == 0 {
+            error!("No matches were found.");
+            exit(1);
+        }
         return Ok(());
     }
 
-    // Displays the interface
-    println!("\n{}\n", beautified_ui);
-
-    if args.log {
-        info!("Searched in: {:?}\n", end - start);
+    if results.is_empty() {
+        print!("\n"); // just adding a new line for better visual
+        error!("No matches were found.");
+        exit(1);
     }
 
-    let selected_path: Option<String> = {
+    // mapping to a different type for terminal output
+    let labels: Vec<String> = results.iter().map(|record| record.label()).collect();
+
+    let selected: Vec<usize> = if args.simple_ui {
+        // Displays the interface
+        println!("\n{}\n", utils::pretty_interface(&labels, true));
+
         // prompting a different message based on the argument given
         match args.open {
             true => println!(
-                "Please select the path via its index to open\n\
+                "Please select the paths via their index to open, such as `1,3,7-12`, `*` or `!4`\n\
 Or press `Enter` to exit"
             ),
 
             false => println!(
-                "Please select the path via its index to copy onto the clipboard\n\
+                "Please select the paths via their index, such as `1,3,7-12`, `*` or `!4`\n\
 Or press `Enter` to exit"
             ),
         }
 
-        utils::user_select(&matches)
+        utils::user_select(&labels).unwrap_or_default()
+    } else {
+        let title: &str = match args.open {
+            true => "Select the paths to open, Tab marks several",
+            false => "Select the paths, Tab marks several",
+        };
+        let paths: Vec<PathBuf> = results.iter().map(|record| record.raw_path.clone()).collect();
+        let previewer = Previewer::new(args.preview_cmd.clone(), path.clone());
+        picker::pick(&labels, &paths, title, &previewer)?
     };
 
-    // selected path
-    let path: String = match selected_path {
-        Some(p) => p,
-        None => return Ok(()), // user didn't select anything
-    };
+    if selected.is_empty() {
+        return Ok(()); // user didn't select anything
+    }
+    let selected: Vec<&Record> = selected.iter().map(|index| &results[*index]).collect();
 
-    if let Some(mut cmd) = args.cmd {
-        cmd = utils::interpolate_to_command(cmd, &path);
-        utils::run_cmd(cmd)?;
-        return Ok(());
+    if let Some(cmd) = &args.cmd {
+        return actions::run_command(cmd, args.batch, &selected, &path);
     }
 
     if args.open {
-        // user wants to open the file
-
-        utils::open_file(&path)?;
-        return Ok(());
+        // user wants to open the files
+        return actions::run(&Action::Open, &selected, &path);
     }
 
-    // An interface to select and copy a path
-    utils::copy(path);
-    println!("Copied path onto the clipboard");
+    // A menu of what to do with the paths, such as copying them
+    let paths: Vec<String> = selected.iter().map(|record| record.path.clone()).collect();
+    if let Some(action) = actions::choose(&menu, &paths)? {
+        actions::run(&action, &selected, &path)?;
+    }
 
     Ok(())
 }
@@ -1,4 +1,8 @@
 //! a custom structure for user input handling
+
+// Using the standard library
+use std::ops::RangeInclusive;
+
 pub struct Options<'o> {
     options: &'o [String],
 }
@@ -9,28 +13,55 @@ impl<'o> Options<'o> {
         Self { options: values }
     }
 
-    /// Evaluates the choice given, checks if choice is a number
-    /// indicating an index, otherwise it will match the internal
-    /// values within self.values
-    pub fn evaluate(&'o self, choice: &str) -> Option<String> {
-        match choice.parse::<usize>() {
-            Ok(int) => {
-                if int <= 0 || int > self.options.len() {
-                    // if integer is less than or equal to 0
-                    // or integer is greater than options length
-                    return None;
-                }
-                return Some(self.options[int - 1].to_owned());
-            }
-            Err(_) => {
-                for el in self.options {
-                    if el == choice {
-                        return Some(el.to_owned());
-                    }
-                }
-                return None;
+    /// Evaluates the choice given, an exact value or indices separated by
+    /// commas, each being a number, a range such as `7-12` or `*` for every
+    /// value. Indices preceded by `!` are left out, starting from every value
+    /// when nothing else is given. Returns the indices of the selected values,
+    /// starting at 0, `None` if the choice is invalid or selects nothing
+    pub fn evaluate(&'o self, choice: &str) -> Option<Vec<usize>> {
+        if let Some(index) = self.options.iter().position(|value| value == choice) {
+            return Some(vec![index]);
+        }
+
+        let mut included: Vec<RangeInclusive<usize>> = Vec::new();
+        let mut excluded: Vec<RangeInclusive<usize>> = Vec::new();
+        for part in choice.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.strip_prefix('!') {
+                Some(part) => excluded.push(self.range(part.trim())?),
+                None => included.push(self.range(part)?),
             }
         }
+        if included.is_empty() && excluded.is_empty() {
+            return None;
+        }
+
+        let selected: Vec<usize> = (0..self.options.len())
+            .filter(|i| included.is_empty() || included.iter().any(|range| range.contains(i)))
+            .filter(|i| !excluded.iter().any(|range| range.contains(i)))
+            .collect();
+
+        (!selected.is_empty()).then_some(selected)
+    }
+
+    /// The indices, starting at 0, of a number, a range or `*`,
+    /// `None` if it is not one of those or is out of bounds
+    fn range(&self, part: &str) -> Option<RangeInclusive<usize>> {
+        let index = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|int| *int != 0 && *int <= self.options.len())
+        };
+
+        if part == "*" {
+            return (!self.options.is_empty()).then(|| 0..=self.options.len() - 1);
+        }
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (index(start)?, index(end)?),
+            None => (index(part)?, index(part)?),
+        };
+        (start <= end).then(|| start - 1..=end - 1)
     }
 
     /// Displays a pretty interface to the options
@@ -42,3 +73,37 @@ impl<'o> Options<'o> {
         buffer.join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_evaluate_ranges() {
+        let values: Vec<String> = (1..=10).map(|i| format!("./{}.rs", i)).collect();
+        let options = Options::new(&values);
+        // numbered from 1, like the choices
+        let indices = |choice: &str| {
+            options
+                .evaluate(choice)
+                .map(|selected| selected.iter().map(|i| i + 1).collect::<Vec<usize>>())
+        };
+
+        assert_eq!(indices("3"), Some(vec![3]));
+        assert_eq!(indices("./7.rs"), Some(vec![7]));
+        assert_eq!(indices("7-9, 1,3"), Some(vec![1, 3, 7, 8, 9]));
+        assert_eq!(indices("*,!2-9"), Some(vec![1, 10]));
+        assert_eq!(indices("!4"), Some(vec![1, 2, 3, 5, 6, 7, 8, 9, 10]));
+        assert_eq!(indices("!1-10"), None);
+        assert_eq!(indices("0"), None);
+        assert_eq!(indices("9-11"), None);
+        assert_eq!(indices("5-3"), None);
+        assert_eq!(indices("1,x"), None);
+
+        // repeated values are told apart by their index
+        let values: Vec<String> = ["./a.rs", "./b.rs", "./a.rs"].map(|v| v.to_string()).to_vec();
+        let options = Options::new(&values);
+        assert_eq!(options.evaluate("3"), Some(vec![2]));
+        assert_eq!(options.evaluate("./a.rs"), Some(vec![0]));
+    }
+}
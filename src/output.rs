@@ -0,0 +1,365 @@
+//! Definition of `Printer`
+//!
+//! Writes matches one at a time as soon as they are found,
+//! either onto the standard output or into the `--output-file`,
+//! as plain paths or in a format meant for other programs.
+
+// Importing from internal modules
+use crate::seek::Entry;
+use crate::seek::FileKind;
+use crate::utils;
+
+// Importing from external modules
+use clap::ValueEnum;
+use serde::Serialize;
+
+// Using the standard library
+use std::fs::OpenOptions;
+use std::io::Result;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+
+/// How matches are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// A path per line
+    #[default]
+    Plain,
+    /// A single array of objects
+    Json,
+    /// An object per line
+    Ndjson,
+    /// A header followed by a row per match
+    Csv,
+}
+
+/// The columns of the CSV format
+const CSV_HEADER: &str = "path,type,size,mtime,score,line,text";
+
+/// A match along with what is known about it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Record {
+    /// The path, lossily converted when it isn't valid UTF-8
+    pub path: String,
+    /// The path as it is on disk, written as it is by plain output
+    #[serde(skip)]
+    pub raw_path: PathBuf,
+    #[serde(rename = "type")]
+    pub kind: FileKind,
+    pub size: u64,
+    /// Seconds since the unix epoch
+    pub mtime: i64,
+    /// The fuzzy score, when ranked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i64>,
+    /// The matching line along with `--contains`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The content of the matching line along with `--line-number`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl Record {
+    pub fn new(entry: &Entry, score: Option<i64>) -> Self {
+        Self {
+            path: entry.path.display().to_string(),
+            raw_path: entry.path.clone(),
+            kind: entry.kind,
+            size: entry.size,
+            mtime: entry.mtime,
+            score,
+            line: None,
+            text: None,
+        }
+    }
+
+    /// How the record is listed, the path along with the matching line if any
+    pub fn label(&self) -> String {
+        format!("{}{}", self.path, self.line_suffix())
+    }
+
+    /// The label holding the path as it is on disk, so paths that
+    /// aren't valid UTF-8 are written out unchanged
+    fn raw_label(&self) -> Vec<u8> {
+        let mut label: Vec<u8> = utils::os_bytes(self.raw_path.as_os_str()).into_owned();
+        label.extend_from_slice(self.line_suffix().as_bytes());
+        label
+    }
+
+    /// What follows the path in the label, the matching line if any
+    fn line_suffix(&self) -> String {
+        match (self.line, &self.text) {
+            (Some(line), Some(text)) => format!(":{}: {}", line, utils::shorten(text.trim(), 200)),
+            _ => String::new(),
+        }
+    }
+
+    /// The record as a row of `CSV_HEADER`
+    fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let kind: String = match self.kind {
+            FileKind::File => "file",
+            FileKind::Dir => "dir",
+            FileKind::Symlink => "symlink",
+            FileKind::Other => "other",
+        }
+        .to_string();
+
+        [
+            self.path.clone(),
+            kind,
+            self.size.to_string(),
+            self.mtime.to_string(),
+            optional(self.score.map(|s| s.to_string())),
+            optional(self.line.map(|l| l.to_string())),
+            optional(self.text.clone()),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+    }
+}
+
+/// Quotes the field when it holds a comma, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A writer of matches, one at a time
+pub struct Printer {
+    writer: Box<dyn Write>,
+    enumerate: bool,
+    format: OutputFormat,
+    /// Whether plain paths are ended by a NUL byte instead of a new line
+    print0: bool,
+    /// Whether every line is ended and flushed right away so matches show up
+    /// immediately, otherwise plain lines are only separated like the previous output files
+    line_buffered: bool,
+    /// A separator to write before the first line
+    prefix: Option<&'static str>,
+    count: usize,
+    /// Raised once whoever was reading the output is gone
+    closed: bool,
+}
+
+impl Printer {
+    /// Prints onto the standard output
+    pub fn stdout(enumerate: bool) -> Self {
+        Self {
+            writer: Box::new(BufWriter::new(io::stdout())),
+            enumerate,
+            format: OutputFormat::Plain,
+            print0: false,
+            line_buffered: true,
+            prefix: None,
+            count: 0,
+            closed: false,
+        }
+    }
+
+    /// Writes into a file, appending to it instead of overwriting if specified
+    pub fn file(loc: &str, append: bool, enumerate: bool) -> Result<Self> {
+        let file = if append {
+            OpenOptions::new().append(true).open(loc)?
+        } else {
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(loc)?
+        };
+
+        Ok(Self {
+            writer: Box::new(BufWriter::new(file)),
+            enumerate,
+            format: OutputFormat::Plain,
+            print0: false,
+            line_buffered: false,
+            // separating the appended result from the existing content
+            prefix: if append { Some("\n") } else { None },
+            count: 0,
+            closed: false,
+        })
+    }
+
+    /// Specifies the format, and whether plain paths are NUL delimited.
+    /// Only plain paths are enumerated
+    pub fn with_format(mut self, format: OutputFormat, print0: bool) -> Self {
+        self.format = format;
+        self.print0 = print0;
+        self
+    }
+
+    /// Writes a single match, a closed pipe is not an error
+    /// but nothing else is written afterwards
+    pub fn print(&mut self, record: &Record) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+
+        let result = match self.format {
+            OutputFormat::Plain => self.write_line(&record.raw_label()),
+            format => self.write_record(format, record),
+        };
+
+        match result {
+            Err(error) if error.kind() == ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> Result<()> {
+        if self.count == 0 {
+            if let Some(prefix) = self.prefix {
+                self.writer.write_all(prefix.as_bytes())?;
+            }
+        } else if !self.line_buffered && !self.print0 {
+            self.writer.write_all(b"\n")?;
+        }
+
+        self.count += 1;
+        if self.enumerate {
+            write!(self.writer, "{}.) ", self.count)?;
+        }
+        self.writer.write_all(line)?;
+
+        if self.print0 {
+            self.writer.write_all(b"\0")?;
+        } else if self.line_buffered {
+            self.writer.write_all(b"\n")?;
+        }
+        if self.line_buffered {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes whatever comes before the first record of a structured format
+    fn write_start(&mut self) -> Result<()> {
+        if let Some(prefix) = self.prefix {
+            self.writer.write_all(prefix.as_bytes())?;
+        }
+        match self.format {
+            OutputFormat::Json => self.writer.write_all(b"[\n"),
+            OutputFormat::Csv => writeln!(self.writer, "{}", CSV_HEADER),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_record(&mut self, format: OutputFormat, record: &Record) -> Result<()> {
+        if self.count == 0 {
+            self.write_start()?;
+        } else if format == OutputFormat::Json {
+            self.writer.write_all(b",\n")?;
+        }
+        self.count += 1;
+
+        match format {
+            OutputFormat::Json => {
+                self.writer.write_all(b"  ")?;
+                serde_json::to_writer(&mut self.writer, record)?;
+            }
+            OutputFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")?;
+            }
+            _ => writeln!(self.writer, "{}", record.to_csv())?,
+        }
+
+        if self.line_buffered {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the reading end of the output is gone
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Ends the output and flushes whatever is left,
+    /// returning the amount of matches written
+    pub fn finish(mut self) -> Result<usize> {
+        if !self.closed {
+            match self.write_end() {
+                Err(error) if error.kind() == ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+        }
+        Ok(self.count)
+    }
+
+    fn write_end(&mut self) -> Result<()> {
+        // structured formats are complete even without matches
+        if self.count == 0 && matches!(self.format, OutputFormat::Json | OutputFormat::Csv) {
+            self.write_start()?;
+        }
+        if self.format == OutputFormat::Json {
+            let end: &[u8] = if self.count == 0 { b"]\n" } else { b"\n]\n" };
+            self.writer.write_all(end)?;
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_record_formats() {
+        let mut record = Record {
+            path: "./a, \"b\".rs".to_string(),
+            raw_path: PathBuf::from("./a, \"b\".rs"),
+            kind: FileKind::File,
+            size: 12,
+            mtime: 1_700_000_000,
+            score: None,
+            line: None,
+            text: None,
+        };
+        assert_eq!(record.to_csv(), "\"./a, \"\"b\"\".rs\",file,12,1700000000,,,");
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"path":"./a, \"b\".rs","type":"file","size":12,"mtime":1700000000}"#
+        );
+
+        record.line = Some(3);
+        record.text = Some("    fn main() {".to_string());
+        assert_eq!(record.label(), "./a, \"b\".rs:3: fn main() {");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn t_plain_paths_are_written_unchanged() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("output");
+        let entry = Entry {
+            path: PathBuf::from(OsStr::from_bytes(b"./caf\xe9.txt")),
+            kind: FileKind::File,
+            size: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            mode: 0,
+        };
+
+        let mut printer = Printer::file(output.to_str().unwrap(), false, true)
+            .unwrap()
+            .with_format(OutputFormat::Plain, true);
+        printer.print(&Record::new(&entry, None)).unwrap();
+        printer.finish().unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"1.) ./caf\xe9.txt\0");
+    }
+}
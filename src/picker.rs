@@ -0,0 +1,568 @@
+//! Contains the `pick()` definition and implementation
+//!
+//! A full screen picker over the matches, drawn on the alternate screen
+//! so the terminal is left as it was. Typing narrows the matches down to
+//! the ones containing the typed characters in order, highlighting them,
+//! while the arrows, or Ctrl-N and Ctrl-P, move between the matches left.
+//! Tab marks several matches to select at once. When the terminal is wide
+//! enough, a preview of the highlighted match is shown next to the matches,
+//! toggled by Ctrl-T. Previews are read on a background thread, so a slow
+//! previewer never holds up typing or moving between the matches.
+//!
+//! The typed filter is smart case, case sensitive only once it
+//! contains an uppercase letter.
+
+// Importing from internal modules
+use crate::preview::Previewer;
+
+// Importing from external modules
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, PrintStyledContent, SetAttribute, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+// Using the standard library
+use std::io::Result;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Rows taken by the title and the filter above the matches
+const HEADER_ROWS: usize = 2;
+/// How wide the terminal must be to show the preview
+const PREVIEW_MIN_WIDTH: usize = 60;
+/// How long a frame waits for the preview of a newly highlighted item,
+/// quick previews are drawn along with it instead of a frame later
+const PREVIEW_WAIT: Duration = Duration::from_millis(30);
+/// How often the picker checks on a pending preview while waiting for keys
+const PREVIEW_POLL: Duration = Duration::from_millis(50);
+/// Shown in place of a preview that isn't ready yet
+const PREVIEW_PENDING: &str = "...";
+
+/// What a key press led to
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Continue,
+    /// The indices of the selected items
+    Selected(Vec<usize>),
+    Cancelled,
+}
+
+/// The positions of the characters of the text matching the filter, `None`
+/// if it doesn't match. A contiguous match is preferred over a scattered one
+fn match_positions(filter: &[char], text: &str) -> Option<Vec<usize>> {
+    let case_sensitive: bool = filter.iter().any(|c| c.is_uppercase());
+    let text: Vec<char> = text
+        .chars()
+        .map(|c| {
+            if case_sensitive {
+                c
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect();
+
+    if filter.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if let Some(start) = text.windows(filter.len()).position(|window| window == filter) {
+        return Some((start..start + filter.len()).collect());
+    }
+
+    let mut positions: Vec<usize> = Vec::with_capacity(filter.len());
+    let mut remaining = filter.iter().peekable();
+    for (i, c) in text.iter().enumerate() {
+        if remaining.peek() == Some(&c) {
+            positions.push(i);
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none().then_some(positions)
+}
+
+/// The item, the amount of rows and the lines of a preview
+type Preview = (usize, usize, Vec<String>);
+
+/// Previews items on a background thread, one at a time. Requests made
+/// while a preview is read are dropped in favor of the latest one
+struct Previews {
+    requests: Sender<(usize, usize, PathBuf)>,
+    results: Receiver<Preview>,
+}
+
+impl Previews {
+    fn spawn(previewer: Previewer) -> Self {
+        let (requests, pending) = mpsc::channel::<(usize, usize, PathBuf)>();
+        let (sender, results) = mpsc::channel::<Preview>();
+
+        // stops along with the picker, once the requests are dropped
+        thread::spawn(move || {
+            while let Ok(mut request) = pending.recv() {
+                // the items highlighted before the last one are out of date
+                while let Ok(latest) = pending.try_recv() {
+                    request = latest;
+                }
+
+                let (index, rows, path) = request;
+                let lines: Vec<String> = previewer.preview(&path, rows);
+                if sender.send((index, rows, lines)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { requests, results }
+    }
+}
+
+/// The state of the picker, apart from the terminal
+struct Picker<'p> {
+    items: &'p [String],
+    /// What was typed, narrowing the items down
+    filter: String,
+    /// The items left, along with the positions of the characters matching the filter
+    visible: Vec<(usize, Vec<usize>)>,
+    /// The position of the highlighted item within `visible`
+    cursor: usize,
+    /// The position of the first item on screen within `visible`
+    offset: usize,
+    /// Whether every item is marked, kept while filtering
+    marked: Vec<bool>,
+    /// How many items fit on screen
+    page: usize,
+    /// The paths previewed for every item, along with what previews them
+    preview: Option<(&'p [PathBuf], Previews)>,
+    show_preview: bool,
+    /// The item and the amount of rows of the latest preview requested
+    requested: Option<(usize, usize)>,
+    /// The latest preview received, so it's not read again on every frame
+    previewed: Option<Preview>,
+}
+
+impl<'p> Picker<'p> {
+    fn new(items: &'p [String]) -> Self {
+        Self {
+            items,
+            filter: String::new(),
+            visible: (0..items.len()).map(|i| (i, Vec::new())).collect(),
+            cursor: 0,
+            offset: 0,
+            marked: vec![false; items.len()],
+            page: 1,
+            preview: None,
+            show_preview: true,
+            requested: None,
+            previewed: None,
+        }
+    }
+
+    /// Previews the highlighted item, `paths` being the path of every item
+    fn with_preview(mut self, paths: &'p [PathBuf], previewer: &Previewer) -> Self {
+        self.preview = Some((paths, Previews::spawn(previewer.clone())));
+        self
+    }
+
+    /// Filters the items again, only the items left are looked
+    /// at when the filter was only appended to
+    fn refilter(&mut self, narrowed: bool) {
+        let filter: Vec<char> = self.filter.chars().collect();
+        let candidates: Vec<usize> = if narrowed {
+            self.visible.iter().map(|(i, _)| *i).collect()
+        } else {
+            (0..self.items.len()).collect()
+        };
+
+        self.visible = candidates
+            .into_iter()
+            .filter_map(|i| match_positions(&filter, &self.items[i]).map(|p| (i, p)))
+            .collect();
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let last = self.visible.len() as isize - 1;
+        self.cursor = (self.cursor as isize + delta).clamp(0, last) as usize;
+    }
+
+    /// Keeps the cursor within the rows on screen
+    fn scroll(&mut self) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + self.page {
+            self.offset = self.cursor + 1 - self.page;
+        }
+    }
+
+    /// Marks or unmarks the highlighted item
+    fn toggle(&mut self) {
+        if let Some((index, _)) = self.visible.get(self.cursor) {
+            self.marked[*index] = !self.marked[*index];
+        }
+    }
+
+    /// The marked items, otherwise the highlighted one
+    fn selection(&self) -> Vec<usize> {
+        let marked: Vec<usize> = (0..self.items.len()).filter(|i| self.marked[*i]).collect();
+        if !marked.is_empty() {
+            return marked;
+        }
+        self.visible.get(self.cursor).map(|(index, _)| vec![*index]).unwrap_or_default()
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Outcome {
+        let control: bool = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = self.page as isize;
+
+        match key.code {
+            KeyCode::Enter => {
+                let selection: Vec<usize> = self.selection();
+                if !selection.is_empty() {
+                    return Outcome::Selected(selection);
+                }
+            }
+            KeyCode::Tab => {
+                self.toggle();
+                self.move_cursor(1);
+            }
+            KeyCode::BackTab => {
+                self.toggle();
+                self.move_cursor(-1);
+            }
+            KeyCode::Esc => return Outcome::Cancelled,
+            KeyCode::Char('c' | 'g') if control => return Outcome::Cancelled,
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('p' | 'k') if control => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('n' | 'j') if control => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::Home => self.move_cursor(-(self.cursor as isize)),
+            KeyCode::End => self.move_cursor(self.visible.len() as isize),
+            KeyCode::Backspace => {
+                if self.filter.pop().is_some() {
+                    self.refilter(false);
+                }
+            }
+            KeyCode::Char('t') if control => self.show_preview = !self.show_preview,
+            KeyCode::Char('u') if control => {
+                self.filter.clear();
+                self.refilter(false);
+            }
+            KeyCode::Char('w') if control => {
+                let kept = self.filter.trim_end().rfind(' ').map(|i| i + 1).unwrap_or(0);
+                self.filter.truncate(kept);
+                self.refilter(false);
+            }
+            KeyCode::Char(c) if !control => {
+                self.filter.push(c);
+                self.refilter(true);
+            }
+            _ => {}
+        }
+
+        Outcome::Continue
+    }
+
+    /// Whether the latest preview received is the one requested last
+    fn is_previewed(&self) -> bool {
+        match (&self.previewed, self.requested) {
+            (Some((index, rows, _)), Some(requested)) => (*index, *rows) == requested,
+            _ => false,
+        }
+    }
+
+    /// Takes the previews read so far, waiting up to `timeout` for the
+    /// requested one. Returns whether it came in, the rest are out of date
+    fn receive_preview(&mut self, timeout: Duration) -> bool {
+        let Some((_, previews)) = &self.preview else {
+            return false;
+        };
+        if self.requested.is_none() || self.is_previewed() {
+            return false;
+        }
+
+        let mut received: Vec<Preview> = previews.results.try_iter().collect();
+        if received.is_empty() {
+            received.extend(previews.results.recv_timeout(timeout));
+        }
+        for preview in received {
+            if Some((preview.0, preview.1)) == self.requested {
+                self.previewed = Some(preview);
+            }
+        }
+        self.is_previewed()
+    }
+
+    /// The preview of the highlighted item, `None` when there is no room for it
+    fn current_preview(&mut self, width: usize) -> Option<Vec<String>> {
+        let (paths, previews) = self.preview.as_ref()?;
+        if !self.show_preview || width < PREVIEW_MIN_WIDTH {
+            return None;
+        }
+        let Some((index, _)) = self.visible.get(self.cursor) else {
+            return Some(Vec::new());
+        };
+
+        let highlighted = (*index, self.page);
+        if self.requested != Some(highlighted) {
+            // the worker is gone only if it panicked
+            let request = (*index, self.page, paths[*index].clone());
+            if previews.requests.send(request).is_err() {
+                return Some(Vec::new());
+            }
+            self.requested = Some(highlighted);
+            self.receive_preview(PREVIEW_WAIT);
+        }
+
+        match &self.previewed {
+            Some((_, _, lines)) if self.is_previewed() => Some(lines.clone()),
+            _ => Some(vec![PREVIEW_PENDING.to_string()]),
+        }
+    }
+
+    /// Draws a whole frame
+    fn render(&mut self, out: &mut impl Write, title: &str) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        self.page = (height as usize).saturating_sub(HEADER_ROWS).max(1);
+        self.scroll();
+
+        queue!(
+            out,
+            MoveTo(0, 0),
+            PrintStyledContent(truncate(title, width).dim()),
+            Clear(ClearType::UntilNewLine)
+        )?;
+
+        let count: String = match self.marked.iter().filter(|m| **m).count() {
+            0 => format!("{}/{}", self.visible.len(), self.items.len()),
+            marked => format!("{} marked {}/{}", marked, self.visible.len(), self.items.len()),
+        };
+        let prompt = format!("> {}", self.filter);
+        queue!(out, MoveTo(0, 1), Print(truncate(&prompt, width)), Clear(ClearType::UntilNewLine))?;
+        if prompt.chars().count() + count.len() + 1 < width {
+            queue!(
+                out,
+                MoveTo((width - count.len()) as u16, 1),
+                PrintStyledContent(count.as_str().dim())
+            )?;
+        }
+
+        // the matches take the left half when previewing
+        let preview: Option<Vec<String>> = self.current_preview(width);
+        let list_width: usize = match preview {
+            Some(_) => width / 2,
+            None => width,
+        };
+
+        for row in 0..self.page {
+            queue!(out, MoveTo(0, (row + HEADER_ROWS) as u16), Clear(ClearType::UntilNewLine))?;
+            let position = self.offset + row;
+            if let Some((index, positions)) = self.visible.get(position) {
+                let selected: bool = position == self.cursor;
+                if selected {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                let cursor: char = if selected { '>' } else { ' ' };
+                let mark: char = if self.marked[*index] { '+' } else { ' ' };
+                queue!(out, Print(format!("{}{} ", cursor, mark)))?;
+
+                for (i, c) in self.items[*index].chars().take(list_width.saturating_sub(3)).enumerate() {
+                    if positions.contains(&i) {
+                        queue!(out, PrintStyledContent(c.yellow().bold()))?;
+                        if selected {
+                            // styled content resets every attribute after it
+                            queue!(out, SetAttribute(Attribute::Reverse))?;
+                        }
+                    } else {
+                        queue!(out, Print(c))?;
+                    }
+                }
+                queue!(out, SetAttribute(Attribute::Reset))?;
+            }
+
+            if let Some(preview) = &preview {
+                let line: &str = preview.get(row).map(|line| line.as_str()).unwrap_or_default();
+                queue!(
+                    out,
+                    MoveTo(list_width as u16, (row + HEADER_ROWS) as u16),
+                    PrintStyledContent("│ ".dim()),
+                    Print(truncate(line, width - list_width - 2))
+                )?;
+            }
+        }
+
+        let column = (prompt.chars().count()).min(width.saturating_sub(1));
+        queue!(out, MoveTo(column as u16, 1), Show)?;
+        out.flush()
+    }
+}
+
+/// Cuts the text down to the width of the terminal
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+/// Restores the terminal when dropped, even when picking failed
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Lets the user pick some of the items, returning their indices,
+/// none if the picker was closed without picking any. `paths` are
+/// previewed along with the item they belong to
+pub fn pick(items: &[String], paths: &[PathBuf], title: &str, previewer: &Previewer) -> Result<Vec<usize>> {
+    let _screen = Screen::enter()?;
+    let mut stdout = io::stdout();
+    let mut picker = Picker::new(items).with_preview(paths, previewer);
+    let mut redraw: bool = true;
+
+    loop {
+        if redraw {
+            picker.render(&mut stdout, title)?;
+        }
+
+        // redrawn once a pending preview comes in
+        if !event::poll(PREVIEW_POLL)? {
+            redraw = picker.receive_preview(Duration::ZERO);
+            continue;
+        }
+        redraw = true;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            // redrawn at the size of the terminal anyway
+            _ => continue,
+        };
+
+        match picker.handle(key) {
+            Outcome::Continue => {}
+            Outcome::Selected(indices) => return Ok(indices),
+            Outcome::Cancelled => return Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// The preview once it was read
+    fn preview(picker: &mut Picker) -> Vec<String> {
+        picker.current_preview(PREVIEW_MIN_WIDTH);
+        picker.receive_preview(Duration::from_secs(5));
+        picker.current_preview(PREVIEW_MIN_WIDTH).unwrap()
+    }
+
+    fn press(picker: &mut Picker, code: KeyCode) -> Outcome {
+        picker.handle(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_in(picker: &mut Picker, text: &str) {
+        for c in text.chars() {
+            press(picker, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn t_match_positions() {
+        let filter = |f: &str| f.chars().collect::<Vec<char>>();
+        assert_eq!(match_positions(&filter("main"), "src/main.rs"), Some(vec![4, 5, 6, 7]));
+        assert_eq!(match_positions(&filter("smr"), "src/main.rs"), Some(vec![0, 4, 9]));
+        assert_eq!(match_positions(&filter("Main"), "src/main.rs"), None);
+        assert_eq!(match_positions(&filter("main"), "src/MAIN.rs"), Some(vec![4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn t_picker_keys() {
+        let items: Vec<String> = ["src/main.rs", "src/lib.rs", "README.md", "src/watch.rs"]
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        let mut picker = Picker::new(&items);
+
+        type_in(&mut picker, "src");
+        assert_eq!(picker.visible.len(), 3);
+        press(&mut picker, KeyCode::Down);
+        press(&mut picker, KeyCode::Down);
+        press(&mut picker, KeyCode::Down);
+        assert_eq!(press(&mut picker, KeyCode::Enter), Outcome::Selected(vec![3]));
+
+        // the rows on screen follow the cursor
+        picker.page = 2;
+        picker.scroll();
+        assert_eq!(picker.offset, 1);
+        press(&mut picker, KeyCode::PageUp);
+        picker.scroll();
+        assert_eq!((picker.cursor, picker.offset), (0, 0));
+
+        // narrowing down resets the cursor, widening brings items back
+        type_in(&mut picker, "l");
+        assert_eq!(picker.visible.len(), 1);
+        press(&mut picker, KeyCode::Backspace);
+        press(&mut picker, KeyCode::Backspace);
+        assert_eq!(picker.visible.len(), 3);
+        picker.handle(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(picker.visible.len(), 4);
+
+        type_in(&mut picker, "zzz");
+        assert_eq!(press(&mut picker, KeyCode::Enter), Outcome::Continue);
+        picker.handle(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+
+        // marked items are kept while filtering
+        press(&mut picker, KeyCode::Tab);
+        type_in(&mut picker, "watch");
+        press(&mut picker, KeyCode::Tab);
+        assert_eq!(press(&mut picker, KeyCode::Enter), Outcome::Selected(vec![0, 3]));
+        assert_eq!(press(&mut picker, KeyCode::Esc), Outcome::Cancelled);
+    }
+
+    #[test]
+    fn t_picker_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, format!("{}\n", name)).unwrap();
+                path
+            })
+            .collect();
+        let items: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        let mut picker = Picker::new(&items).with_preview(&paths, &Previewer::default());
+        picker.page = 10;
+
+        assert_eq!(preview(&mut picker), ["a.txt"]);
+        press(&mut picker, KeyCode::Down);
+        assert_eq!(preview(&mut picker), ["b.txt"]);
+        // no room for it
+        assert_eq!(picker.current_preview(PREVIEW_MIN_WIDTH - 1), None);
+
+        // the preview of another item is never shown
+        picker.previewed = Some((0, 10, vec!["a.txt".to_string()]));
+        assert_eq!(picker.current_preview(PREVIEW_MIN_WIDTH).unwrap(), [PREVIEW_PENDING]);
+    }
+}
@@ -0,0 +1,202 @@
+//! Definition of `Previewer`
+//!
+//! What the picker shows of the highlighted match: the first lines of
+//! text files, the entries of directories, the target of symbolic links
+//! and a summary of the metadata of binaries. A `--preview-cmd` template
+//! replaces all of those with the output of an external previewer.
+
+// Importing from internal modules
+use crate::seek::is_binary;
+use crate::utils;
+use crate::utils::Target;
+
+// Using the standard library
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata};
+use std::io::Read;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How much of a text file is read to preview it
+const PREVIEW_BYTES: u64 = 64 * 1024;
+/// How many spaces a tab is expanded to
+const TAB_WIDTH: usize = 4;
+/// How long an external previewer may run before it's killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Describes the highlighted match in the picker
+#[derive(Debug, Clone, Default)]
+pub struct Previewer {
+    /// An external previewer, interpolated like `--cmd`
+    command: Option<String>,
+    /// The starting path, which `{root}` is relative to
+    root: PathBuf,
+}
+
+impl Previewer {
+    pub fn new(command: Option<String>, root: PathBuf) -> Self {
+        Self { command, root }
+    }
+
+    /// At most `rows` lines previewing the path, errors are previewed as well
+    pub fn preview(&self, path: &Path, rows: usize) -> Vec<String> {
+        if rows == 0 {
+            return Vec::new();
+        }
+
+        let lines: Result<Vec<String>> = match &self.command {
+            Some(cmd) => {
+                let cmd: OsString = utils::interpolate(cmd, &[Target { path, line: None }], &self.root);
+                utils::cmd_output(cmd, COMMAND_TIMEOUT)
+                    .map(|output| output.lines().map(|line| line.to_string()).collect())
+            }
+            None => describe(path, rows),
+        };
+
+        match lines {
+            Ok(lines) => lines.iter().take(rows).map(|line| printable(line)).collect(),
+            Err(error) => vec![error.to_string()],
+        }
+    }
+}
+
+fn describe(path: &Path, rows: usize) -> Result<Vec<String>> {
+    let metadata: Metadata = fs::symlink_metadata(path)?;
+
+    if metadata.is_symlink() {
+        return Ok(vec![format!("-> {}", fs::read_link(path)?.display())]);
+    }
+    if metadata.is_dir() {
+        return list_dir(path, rows);
+    }
+
+    let mut content: Vec<u8> = Vec::new();
+    File::open(path)?.take(PREVIEW_BYTES).read_to_end(&mut content)?;
+    if is_binary(&content) {
+        return Ok(summary(&metadata));
+    }
+
+    Ok(String::from_utf8_lossy(&content)
+        .lines()
+        .take(rows)
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// The names of the entries, directories first and marked by a trailing `/`
+fn list_dir(path: &Path, rows: usize) -> Result<Vec<String>> {
+    let mut entries: Vec<(bool, String)> = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let is_dir: bool = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            (!is_dir, entry.file_name().to_string_lossy().into_owned())
+        })
+        .collect();
+    entries.sort();
+
+    if entries.is_empty() {
+        return Ok(vec!["(empty directory)".to_string()]);
+    }
+
+    let mut lines: Vec<String> = entries
+        .iter()
+        .map(|(is_file, name)| if *is_file { name.clone() } else { format!("{}/", name) })
+        .collect();
+    if lines.len() > rows {
+        let more: usize = lines.len() - rows + 1;
+        lines.truncate(rows - 1);
+        lines.push(format!("... {} more", utils::format_num(more)));
+    }
+    Ok(lines)
+}
+
+/// The size, modification time and permissions of a binary
+fn summary(metadata: &Metadata) -> Vec<String> {
+    let mut lines: Vec<String> = vec![
+        "(binary file)".to_string(),
+        format!("size: {} bytes", utils::format_num(metadata.len() as usize)),
+    ];
+    if let Ok(modified) = metadata.modified() {
+        lines.push(format!("modified: {}", humantime::format_rfc3339_seconds(modified)));
+    }
+    lines.push(format!("permissions: {}", permissions(metadata)));
+    lines
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode: u32 = metadata.permissions().mode();
+    let flags: String = (0..9)
+        .map(|i| match mode & (1 << (8 - i)) {
+            0 => '-',
+            _ => ['r', 'w', 'x'][i % 3],
+        })
+        .collect();
+    format!("{:o} {}", mode & 0o777, flags)
+}
+
+#[cfg(not(unix))]
+fn permissions(metadata: &Metadata) -> String {
+    match metadata.permissions().readonly() {
+        true => "read only".to_string(),
+        false => "read and write".to_string(),
+    }
+}
+
+/// Expands tabs and leaves out escape sequences and control
+/// characters, which would otherwise be interpreted by the terminal
+fn printable(line: &str) -> String {
+    let mut printable = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' => printable.push_str(&" ".repeat(TAB_WIDTH)),
+            // a control sequence ends with a character within `@` and `~`
+            '\x1b' => {
+                if chars.next() == Some('[') {
+                    chars.by_ref().find(|c| ('@'..='~').contains(c));
+                }
+            }
+            c if c.is_control() => {}
+            c => printable.push(c),
+        }
+    }
+    printable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn t_preview() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {\n\tlet a = 1;\x1b[2J\x07\n}\n").unwrap();
+        fs::write(root.join("blob.bin"), b"\x7fELF\0\x01").unwrap();
+        fs::write(root.join("z.txt"), "").unwrap();
+
+        let previewer = Previewer::default();
+        let preview = |path: &Path, rows: usize| previewer.preview(path, rows);
+
+        assert_eq!(preview(&root.join("main.rs"), 2), ["fn main() {", "    let a = 1;"]);
+        assert_eq!(preview(&root, 10), ["sub/", "blob.bin", "main.rs", "z.txt"]);
+        assert_eq!(preview(&root, 3), ["sub/", "blob.bin", "... 2 more"]);
+        assert_eq!(preview(&root.join("blob.bin"), 10)[..2], ["(binary file)", "size: 6 bytes"]);
+        assert!(preview(&root.join("missing"), 10)[0].contains("No such file"));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("main.rs", root.join("link")).unwrap();
+            assert_eq!(preview(&root.join("link"), 10), ["-> main.rs"]);
+            let command = Previewer::new(Some("printf '%s\\n' {root} x".to_string()), root.clone());
+            assert_eq!(command.preview(&root.join("a b"), 1), ["a b"]);
+        }
+    }
+}
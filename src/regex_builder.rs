@@ -1,28 +1,153 @@
 //! Modifies and builds the regex
 
 // Importing from external libraries
+use clap::ValueEnum;
+use globset::GlobBuilder;
+use regex::bytes;
 use regex::escape;
 use regex::Regex;
 use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
 
 // Making use of the standard library
 use std::io::Result;
 use std::io::{Error, ErrorKind};
 
+/// How letters of different case are matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    Insensitive,
+    Sensitive,
+    /// Sensitive only when the pattern contains an uppercase letter
+    #[default]
+    Smart,
+}
+
+impl CaseMode {
+    /// Whether the pattern is matched case sensitively. Escaped characters,
+    /// such as `\S`, `\pL` or `\p{Lu}`, don't count as uppercase letters
+    pub fn is_case_sensitive(self, pattern: &str) -> bool {
+        match self {
+            CaseMode::Insensitive => false,
+            CaseMode::Sensitive => true,
+            CaseMode::Smart => {
+                let mut chars = pattern.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if c == '\\' {
+                        // `\p{..}` and `\P{..}` name a whole class, `\pL` a single letter one
+                        if matches!(chars.next(), Some('p' | 'P')) && chars.next() == Some('{') {
+                            chars.by_ref().find(|c| *c == '}');
+                        }
+                        continue;
+                    }
+                    if c.is_uppercase() {
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
 /// given the regex string query, if exact is true,
 /// the function will escape any special characters, matching the raw string
-pub fn build_regex(query: String, case_sensitive: bool, exact: bool) -> Result<Regex> {
+pub fn build_regex(query: String, case: CaseMode, exact: bool) -> Result<Regex> {
+    // deciding on the query as written, before escaping it
+    let case_sensitive: bool = case.is_case_sensitive(&query);
     let query = if exact {
         format!("^{}$", escape(&query))
     } else {
         query
     };
     return match RegexBuilder::new(&query)
-        // inverting the booleans to match logic
-        .case_insensitive(!case_sensitive || !exact)
+        .case_insensitive(!case_sensitive)
         .build()
     {
         Ok(reg) => Ok(reg),
         Err(error) => Err(Error::new(ErrorKind::Other, error.to_string())),
     };
 }
+
+/// Builds the regex of a glob pattern, which must match the whole string.
+/// `*` and `?` don't match a path separator, `**` does.
+///
+/// The regex works on bytes, like the globs of `globset` it comes from
+pub fn build_glob(glob: &str, case: CaseMode) -> Result<bytes::Regex> {
+    let glob_regex = match GlobBuilder::new(glob)
+        .literal_separator(true)
+        .case_insensitive(!case.is_case_sensitive(glob))
+        .build()
+    {
+        Ok(glob_regex) => glob_regex,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid glob `{}`: {}", glob, error.kind()),
+            ))
+        }
+    };
+
+    return match bytes::Regex::new(glob_regex.regex()) {
+        Ok(reg) => Ok(reg),
+        Err(error) => Err(Error::new(ErrorKind::Other, error.to_string())),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(glob: &str, text: &str) -> bool {
+        build_glob(glob, CaseMode::Sensitive).unwrap().is_match(text.as_bytes())
+    }
+
+    #[test]
+    fn t_glob_wildcards() {
+        assert!(is_match("*.rs", "main.rs"));
+        assert!(!is_match("*.rs", "main.rs.bak"));
+        assert!(!is_match("*.rs", "src/main.rs"));
+        assert!(is_match("**/*.rs", "src/seek/main.rs"));
+        assert!(is_match("**/*.rs", "main.rs"));
+        assert!(is_match("src/**", "src/seek/main.rs"));
+        assert!(is_match("?.txt", "a.txt"));
+        assert!(!is_match("?.txt", "ab.txt"));
+        assert!(is_match("file[0-9].[!c]*", "file7.rs"));
+        assert!(!is_match("file[0-9].[!c]*", "file7.c"));
+        assert!(is_match("*.{rs,toml}", "Cargo.toml"));
+        assert!(is_match("é*.txt", "été.txt"));
+        assert!(!build_glob("*.RS", CaseMode::Smart).unwrap().is_match(b"main.rs"));
+        assert!(build_glob("*.RS", CaseMode::Insensitive).unwrap().is_match(b"main.rs"));
+
+        let error = build_glob("[abc", CaseMode::Smart).unwrap_err().to_string();
+        assert_eq!(error, "Invalid glob `[abc`: unclosed character class; missing ']'");
+    }
+
+    #[test]
+    fn t_case_modes() {
+        let is_match = |query: &str, case: CaseMode, exact: bool, text: &str| {
+            build_regex(query.to_string(), case, exact).unwrap().is_match(text)
+        };
+
+        for exact in [false, true] {
+            assert!(is_match("readme.md", CaseMode::Insensitive, exact, "README.md"));
+            assert!(is_match("README.md", CaseMode::Insensitive, exact, "readme.md"));
+
+            assert!(!is_match("readme.md", CaseMode::Sensitive, exact, "README.md"));
+            assert!(is_match("README.md", CaseMode::Sensitive, exact, "README.md"));
+
+            // lowercase queries stay insensitive, uppercase ones turn sensitive
+            assert!(is_match("readme.md", CaseMode::Smart, exact, "README.md"));
+            assert!(is_match("README.md", CaseMode::Smart, exact, "README.md"));
+            assert!(!is_match("README.md", CaseMode::Smart, exact, "readme.md"));
+        }
+
+        // escaped characters aren't letters of the query
+        assert!(!CaseMode::Smart.is_case_sensitive(r"\S+\.rs"));
+        assert!(!CaseMode::Smart.is_case_sensitive(r"\p{Lu}x"));
+        assert!(!CaseMode::Smart.is_case_sensitive(r"\pL"));
+        assert!(!CaseMode::Smart.is_case_sensitive(r"\PNx"));
+        assert!(CaseMode::Smart.is_case_sensitive(r"\pLX"));
+    }
+}
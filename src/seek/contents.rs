@@ -0,0 +1,196 @@
+//! Definition of `ContentSearch`
+//!
+//! Looks inside the files that already matched the query, keeping only
+//! those whose contents match a regex along with the matching lines.
+//! Files are read a line at a time, stopping at the first match unless
+//! every matching line is wanted. Binary files, told apart by a NUL byte
+//! within their first few kilobytes like git does, are skipped.
+
+// importing from external crates
+use regex::Regex;
+use tokio::task::spawn_blocking;
+use tokio::task::JoinHandle;
+
+// Importing local modules
+use crate::seek::Entry;
+use crate::seek::FileKind;
+
+// Use of the standard library
+use std::fs;
+use std::fs::File;
+use std::io::Result;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::thread;
+
+/// How much of a file is looked at to tell whether it's binary
+const BINARY_PROBE: usize = 8 * 1024;
+/// How many files every core searches at a time when only a few matches are needed
+const BATCH_PER_CORE: usize = 16;
+
+/// Whether the content is binary, holding a NUL byte within its first few kilobytes
+pub fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_PROBE)].contains(&0)
+}
+
+/// A line of a file that matched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    /// Starting at 1
+    pub number: usize,
+    pub line: String,
+}
+
+/// A match along with the lines that matched within it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentMatch {
+    pub entry: Entry,
+    pub lines: Vec<LineMatch>,
+}
+
+/// The regex searched for within files
+#[derive(Debug, Clone)]
+pub struct ContentSearch {
+    regex: Regex,
+    /// Larger files are skipped
+    max_filesize: u64,
+    /// Whether every matching line is wanted, otherwise only the first one
+    all_lines: bool,
+}
+
+impl ContentSearch {
+    pub fn new(regex: Regex, max_filesize: u64, all_lines: bool) -> Self {
+        Self {
+            regex,
+            max_filesize,
+            all_lines,
+        }
+    }
+
+    /// The lines of the file matching the regex, `None` if none did or
+    /// the file is skipped: unreadable, binary or larger than allowed
+    pub fn search(&self, path: &Path) -> Option<Vec<LineMatch>> {
+        // symbolic links are followed, reading a directory fails
+        let metadata = fs::metadata(path).ok()?;
+        if !metadata.is_file() || metadata.len() > self.max_filesize {
+            return None;
+        }
+
+        let mut reader = BufReader::with_capacity(BINARY_PROBE, File::open(path).ok()?);
+        if is_binary(reader.fill_buf().ok()?) {
+            return None;
+        }
+
+        let mut lines: Vec<LineMatch> = Vec::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut number: usize = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer).ok()? == 0 {
+                break;
+            }
+            number += 1;
+
+            let line = String::from_utf8_lossy(&buffer);
+            let line: &str = line.strip_suffix('\n').unwrap_or(&line);
+            let line: &str = line.strip_suffix('\r').unwrap_or(line);
+            if self.regex.is_match(line) {
+                lines.push(LineMatch {
+                    number,
+                    line: line.to_string(),
+                });
+                if !self.all_lines {
+                    break;
+                }
+            }
+        }
+
+        (!lines.is_empty()).then_some(lines)
+    }
+}
+
+/// Searches the contents of a batch of entries on every core, keeping their order
+async fn search_batch(entries: &[Entry], search: &ContentSearch, cores_amount: usize) -> Result<Vec<ContentMatch>> {
+    let chunk_size: usize = entries.len().div_ceil(cores_amount).max(1);
+    let mut workers: Vec<JoinHandle<Vec<ContentMatch>>> = Vec::new();
+
+    // contiguous chunks rather than `utils::distribute`, sorted matches stay sorted
+    for chunk in entries.chunks(chunk_size) {
+        let chunk: Vec<Entry> = chunk.to_vec();
+        let search = search.clone();
+        workers.push(spawn_blocking(move || {
+            chunk
+                .into_iter()
+                .filter(|entry| entry.kind != FileKind::Dir)
+                .filter_map(|entry| {
+                    let lines = search.search(&entry.path)?;
+                    Some(ContentMatch { entry, lines })
+                })
+                .collect()
+        }));
+    }
+
+    let mut found_result: Vec<ContentMatch> = Vec::new();
+    for worker in workers {
+        found_result.extend(worker.await?);
+    }
+    Ok(found_result)
+}
+
+/// Searches the contents of the entries on every core, keeping their order.
+/// Directories never match. Given a limit, the entries are searched a batch
+/// at a time, stopping once enough of them matched
+pub async fn search_contents(
+    entries: &[Entry],
+    search: &ContentSearch,
+    limit: Option<usize>,
+) -> Result<Vec<ContentMatch>> {
+    let cores_amount: usize = thread::available_parallelism()?.into();
+    let batch_size: usize = match limit {
+        Some(_) => cores_amount * BATCH_PER_CORE,
+        None => entries.len().max(1),
+    };
+
+    let mut found_result: Vec<ContentMatch> = Vec::new();
+    for batch in entries.chunks(batch_size) {
+        found_result.extend(search_batch(batch, search, cores_amount).await?);
+
+        if let Some(limit) = limit.filter(|limit| found_result.len() >= *limit) {
+            found_result.truncate(limit);
+            break;
+        }
+    }
+    Ok(found_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn t_content_search() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::write(root.join("main.rs"), "use std::io;\n\nfn main() {\n    todo!()\n}\n").unwrap();
+        fs::write(root.join("blob.bin"), b"fn main\0\x01\x02").unwrap();
+
+        let search = ContentSearch::new(Regex::new(r"fn \w+|todo").unwrap(), u64::MAX, true);
+        let lines = search.search(&root.join("main.rs")).unwrap();
+        assert_eq!(
+            lines.iter().map(|l| l.number).collect::<Vec<usize>>(),
+            vec![3, 4]
+        );
+        assert_eq!(lines[0].line, "fn main() {");
+
+        // without every line, the file is only read up to its first match
+        let first = ContentSearch::new(Regex::new(r"fn \w+|todo").unwrap(), u64::MAX, false);
+        assert_eq!(first.search(&root.join("main.rs")).unwrap(), lines[..1]);
+
+        // binaries, directories and files that are too large are skipped
+        assert!(search.search(&root.join("blob.bin")).is_none());
+        assert!(search.search(&root).is_none());
+        let small = ContentSearch::new(Regex::new("fn").unwrap(), 10, true);
+        assert!(small.search(&root.join("main.rs")).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+// Definition of `Entry`
+//
+// A path along with the metadata captured
+// while scanning, so filters never hit the disk
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::fs;
+use std::fs::Metadata;
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+/// The type of object an entry is, symbolic links are not followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    /// Sockets, pipes, devices and such
+    Other,
+}
+
+/// A scanned path and its metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub kind: FileKind,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time in seconds since the unix epoch
+    pub mtime: i64,
+    /// Nanoseconds within the second of the last modification,
+    /// telling apart changes made within the same second
+    #[serde(default)]
+    pub mtime_nsec: u32,
+    /// Permission bits, on Windows only the read-only bit is reflected
+    pub mode: u32,
+}
+
+/// Paths that aren't valid UTF-8 are written lossily
+/// instead of failing to write the whole cache
+pub fn serialize_path<S: Serializer>(path: &Path, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode_of(metadata: &Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+impl Entry {
+    /// Captures the metadata of a path without following symbolic links
+    pub fn from_path(path: &Path) -> Result<Self> {
+        Ok(Self::from_metadata(path.to_path_buf(), &fs::symlink_metadata(path)?))
+    }
+
+    /// Captures the metadata of an entry read from a directory
+    pub fn from_dir_entry(entry: &fs::DirEntry) -> Result<Self> {
+        // `DirEntry::metadata` doesn't follow symbolic links
+        Ok(Self::from_metadata(entry.path(), &entry.metadata()?))
+    }
+
+    fn from_metadata(path: PathBuf, metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+        let kind = if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+
+        let (mtime, mtime_nsec): (i64, u32) = match metadata.modified() {
+            Ok(time) => match time.duration_since(UNIX_EPOCH) {
+                Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+                Err(before) => (-(before.duration().as_secs() as i64), 0),
+            },
+            Err(_) => (0, 0),
+        };
+
+        Self {
+            path,
+            kind,
+            size: metadata.len(),
+            mtime,
+            mtime_nsec,
+            mode: mode_of(metadata),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
+    }
+
+    /// Whether both entries were last modified at the exact same time
+    pub fn same_mtime(&self, other: &Entry) -> bool {
+        self.mtime == other.mtime && self.mtime_nsec == other.mtime_nsec
+    }
+}
+
+impl AsRef<Path> for Entry {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
@@ -0,0 +1,118 @@
+//! Definition of `Exclusions`
+//!
+//! Decides what entries are left out of a scan based on the
+//! `--exclude` names and the `--exclude-glob` patterns.
+
+// Importing from external crates
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+// standard library
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// Names and glob patterns of entries to exclude
+///
+/// A glob without a path separator is matched against the name of the
+/// entry, otherwise it's matched against the path relative to the scan root
+#[derive(Debug, Clone)]
+pub struct Exclusions {
+    names: Vec<String>,
+    name_globs: GlobSet,
+    path_globs: GlobSet,
+}
+
+impl Exclusions {
+    /// Compiles the exclusions, erroring on an invalid glob
+    pub fn new(names: &[String], globs: &[String]) -> Result<Self> {
+        let mut name_globs = GlobSetBuilder::new();
+        let mut path_globs = GlobSetBuilder::new();
+
+        for pattern in globs {
+            let glob = match GlobBuilder::new(pattern).literal_separator(true).build() {
+                Ok(glob) => glob,
+                Err(error) => return Err(Error::new(ErrorKind::InvalidInput, error.to_string())),
+            };
+
+            if pattern.contains('/') {
+                path_globs.add(glob);
+            } else {
+                name_globs.add(glob);
+            }
+        }
+
+        let build = |builder: GlobSetBuilder| -> Result<GlobSet> {
+            builder
+                .build()
+                .map_err(|error| Error::new(ErrorKind::InvalidInput, error.to_string()))
+        };
+
+        Ok(Self {
+            names: names.to_vec(),
+            name_globs: build(name_globs)?,
+            path_globs: build(path_globs)?,
+        })
+    }
+
+    /// Returns true if there is nothing to exclude
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.name_globs.is_empty() && self.path_globs.is_empty()
+    }
+
+    /// Checks the entry itself, not its ancestors, meant to
+    /// be used while walking where ancestors were already checked
+    pub fn is_excluded(&self, path: &Path, root: &Path) -> bool {
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name,
+            None => return false,
+        };
+
+        if self.names.iter().any(|n| n == name) || self.name_globs.is_match(name) {
+            return true;
+        }
+
+        if self.path_globs.is_empty() {
+            return false;
+        }
+
+        match path.strip_prefix(root) {
+            Ok(relative) => self.path_globs.is_match(relative),
+            Err(_) => self.path_globs.is_match(path),
+        }
+    }
+
+    /// Checks the entry and all of its ancestors up to the root, meant to be
+    /// used on paths that didn't go through the walk, such as cached paths
+    pub fn is_excluded_any(&self, path: &Path, root: &Path) -> bool {
+        path.ancestors()
+            .take_while(|ancestor| *ancestor != root)
+            .any(|ancestor| self.is_excluded(ancestor, root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_excluded_by_name_and_glob() {
+        let exclusions = Exclusions::new(
+            &["node_modules".to_string()],
+            &["*.min.js".to_string(), "src/gen/**".to_string()],
+        )
+        .unwrap();
+        let root = Path::new("/project");
+
+        assert!(exclusions.is_excluded(Path::new("/project/web/node_modules"), root));
+        assert!(exclusions.is_excluded(Path::new("/project/web/app.min.js"), root));
+        assert!(exclusions.is_excluded(Path::new("/project/src/gen/a.rs"), root));
+        assert!(!exclusions.is_excluded(Path::new("/project/src/main.rs"), root));
+        assert!(!exclusions.is_excluded(Path::new("/project/web/node_modules/a.js"), root));
+        assert!(exclusions.is_excluded_any(Path::new("/project/web/node_modules/a.js"), root));
+    }
+
+    #[test]
+    fn t_invalid_glob() {
+        assert!(Exclusions::new(&[], &["[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+//! Definition of `Expression`
+//!
+//! A small query language combining several patterns, such as:
+//!
+//! ```text
+//! name:/test/ and ext:rs and not path:target
+//! (ext:rs or ext:toml) and not type:dir
+//! ```
+//!
+//! Terms are written as `field:value`, a term without a field targets the name:
+//!
+//! - `name` the base name
+//! - `path` the path relative to the starting path
+//! - `ext` the extension, without the leading period
+//! - `type` one of `file`, `dir`, `symlink` or `other`
+//!
+//! A value within slashes, `/.../`, is a regex, otherwise it's a literal
+//! that must appear within the name or the path, or be the whole extension.
+//! Values with spaces can be quoted, `"..."`.
+//!
+//! Terms are combined with `and`, `or` and `not`, grouped with parentheses.
+//! `not` binds the tightest, then `and`, then `or`.
+//!
+//! Every regex is compiled into a single `RegexSet` per field, so an entry
+//! is checked against all the patterns of a field at once.
+
+// Importing from external crates
+use regex::escape;
+use regex::{RegexSet, RegexSetBuilder, SetMatches};
+
+// Importing local modules
+use crate::regex_builder::CaseMode;
+use crate::seek::search::get_base_name;
+use crate::seek::Entry;
+use crate::seek::FileKind;
+
+// Use of the standard library
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::CharIndices;
+
+/// The part of an entry a pattern is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Path,
+    Ext,
+}
+
+impl Field {
+    const ALL: [Field; 3] = [Field::Name, Field::Path, Field::Ext];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// A term, `field` is `None` when none was written
+    Term {
+        field: Option<String>,
+        value: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Regex(String),
+    Literal(String),
+}
+
+/// The evaluated tree, leaves refer to a pattern of a `RegexSet`
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Pattern { field: Field, index: usize },
+    Kind(FileKind),
+}
+
+/// A compiled query expression
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Node,
+    /// The patterns of every field, in the order of `Field::ALL`
+    sets: Vec<RegexSet>,
+}
+
+fn invalid(source: &str, column: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "Invalid expression `{}`: {} at column {}",
+            source,
+            message,
+            column + 1
+        ),
+    )
+}
+
+/// Splits the source into tokens along with the column they start at
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut chars: Peekable<CharIndices> = source.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push((start, Token::LeftParen));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((start, Token::RightParen));
+            }
+            '/' | '"' => {
+                let value = read_value(source, &mut chars)?;
+                tokens.push((start, Token::Term { field: None, value }));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == ':' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                if let Some(&(column, ':')) = chars.peek() {
+                    chars.next();
+                    if !matches!(word.as_str(), "name" | "path" | "ext" | "type") {
+                        return Err(invalid(
+                            source,
+                            start,
+                            &format!(
+                                "unknown field `{}`, expected `name`, `path`, `ext` or `type`",
+                                word
+                            ),
+                        ));
+                    }
+                    match chars.peek() {
+                        Some(&(_, c)) if !c.is_whitespace() && c != ')' => {}
+                        _ => {
+                            return Err(invalid(
+                                source,
+                                column + 1,
+                                &format!("expected a value after `{}:`", word),
+                            ))
+                        }
+                    }
+                    let value = read_value(source, &mut chars)?;
+                    tokens.push((start, Token::Term { field: Some(word), value }));
+                    continue;
+                }
+
+                let token = match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Term {
+                        field: None,
+                        value: Value::Literal(word),
+                    },
+                };
+                tokens.push((start, token));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a `/regex/`, a `"quoted literal"` or a bare literal
+fn read_value(source: &str, chars: &mut Peekable<CharIndices>) -> Result<Value> {
+    let (start, first) = match chars.peek() {
+        Some(&(start, first)) => (start, first),
+        None => return Err(invalid(source, source.len(), "expected a value")),
+    };
+
+    if first == '/' || first == '"' {
+        chars.next();
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some((_, c)) if c == first => break,
+                Some((_, '\\')) => match chars.next() {
+                    // an escaped delimiter is the delimiter itself
+                    Some((_, c)) if c == first => value.push(c),
+                    Some((_, c)) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => value.push('\\'),
+                },
+                Some((_, c)) => value.push(c),
+                None => {
+                    let what = if first == '/' { "regex" } else { "quote" };
+                    return Err(invalid(source, start, &format!("unclosed {}", what)));
+                }
+            }
+        }
+
+        return Ok(if first == '/' {
+            Value::Regex(value)
+        } else {
+            Value::Literal(value)
+        });
+    }
+
+    let mut value = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if c.is_whitespace() || c == ')' {
+            break;
+        }
+        value.push(c);
+        chars.next();
+    }
+    Ok(Value::Literal(value))
+}
+
+/// A recursive descent parser over the tokens
+struct Parser<'p> {
+    source: &'p str,
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// The patterns of every field, in the order of `Field::ALL`
+    patterns: Vec<Vec<String>>,
+    case: CaseMode,
+}
+
+impl<'p> Parser<'p> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    /// The column of the current token, or the end of the source
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|(column, _)| *column)
+            .unwrap_or(self.source.len())
+    }
+
+    fn error(&self, message: &str) -> Error {
+        invalid(self.source, self.column(), message)
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut node = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error("expected a term")),
+        };
+
+        match token {
+            Token::Not => {
+                self.position += 1;
+                Ok(Node::Not(Box::new(self.unary()?)))
+            }
+            Token::LeftParen => {
+                let opening = self.column();
+                self.position += 1;
+                let node = self.or()?;
+                if self.peek() != Some(&Token::RightParen) {
+                    return Err(invalid(self.source, opening, "unclosed `(`"));
+                }
+                self.position += 1;
+                Ok(node)
+            }
+            Token::Term { field, value } => {
+                let node = self.term(field.as_deref(), value)?;
+                self.position += 1;
+                Ok(node)
+            }
+            Token::RightParen => Err(self.error("unexpected `)`")),
+            Token::And | Token::Or => Err(self.error("expected a term before the operator")),
+        }
+    }
+
+    fn term(&mut self, field: Option<&str>, value: Value) -> Result<Node> {
+        let field = match field {
+            None | Some("name") => Field::Name,
+            Some("path") => Field::Path,
+            Some("ext") => Field::Ext,
+            Some(_) => {
+                let kind = match &value {
+                    Value::Literal(kind) => kind.to_lowercase(),
+                    Value::Regex(_) => String::new(),
+                };
+                return match kind.as_str() {
+                    "f" | "file" => Ok(Node::Kind(FileKind::File)),
+                    "d" | "dir" | "directory" => Ok(Node::Kind(FileKind::Dir)),
+                    "l" | "symlink" | "link" => Ok(Node::Kind(FileKind::Symlink)),
+                    "o" | "other" => Ok(Node::Kind(FileKind::Other)),
+                    _ => Err(self.error("expected `file`, `dir`, `symlink` or `other` as the type")),
+                };
+            }
+        };
+
+        // every pattern decides on its own whether it's case sensitive
+        let case_sensitive: bool = match &value {
+            Value::Regex(text) | Value::Literal(text) => self.case.is_case_sensitive(text),
+        };
+        let pattern = match (field, value) {
+            (_, Value::Regex(regex)) => regex,
+            (Field::Ext, Value::Literal(ext)) => format!("^{}$", escape(ext.trim_start_matches('.'))),
+            (_, Value::Literal(literal)) => escape(&literal),
+        };
+
+        let patterns = &mut self.patterns[field.index()];
+        patterns.push(if case_sensitive {
+            pattern
+        } else {
+            format!("(?i:{})", pattern)
+        });
+        Ok(Node::Pattern {
+            field,
+            index: patterns.len() - 1,
+        })
+    }
+}
+
+impl Expression {
+    /// Parses and compiles the expression
+    pub fn parse(source: &str, case: CaseMode) -> Result<Self> {
+        let mut parser = Parser {
+            source,
+            tokens: tokenize(source)?,
+            position: 0,
+            patterns: vec![Vec::new(); Field::ALL.len()],
+            case,
+        };
+
+        let root = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return Err(parser.error("expected `and`, `or` or the end of the expression"));
+        }
+
+        let mut sets: Vec<RegexSet> = Vec::new();
+        for patterns in parser.patterns.iter() {
+            let set = RegexSetBuilder::new(patterns)
+                .build()
+                .map_err(|error| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid expression `{}`: {}", source, error),
+                    )
+                })?;
+            sets.push(set);
+        }
+
+        Ok(Self { root, sets })
+    }
+
+    /// Evaluates the expression, `relative` being the path relative to the starting path
+    pub fn is_match(&self, entry: &Entry, relative: &Path) -> bool {
+        let subjects: [String; 3] = [
+            get_base_name(&entry.path),
+            relative.to_string_lossy().into_owned(),
+            entry
+                .path
+                .extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        ];
+
+        // every pattern of a field is checked at once
+        let matches: Vec<Option<SetMatches>> = Field::ALL
+            .iter()
+            .map(|field| {
+                let set = &self.sets[field.index()];
+                (!set.is_empty()).then(|| set.matches(&subjects[field.index()]))
+            })
+            .collect();
+
+        evaluate(&self.root, entry, &matches)
+    }
+}
+
+fn evaluate(node: &Node, entry: &Entry, matches: &[Option<SetMatches>]) -> bool {
+    match node {
+        Node::And(left, right) => evaluate(left, entry, matches) && evaluate(right, entry, matches),
+        Node::Or(left, right) => evaluate(left, entry, matches) || evaluate(right, entry, matches),
+        Node::Not(node) => !evaluate(node, entry, matches),
+        Node::Pattern { field, index } => matches[field.index()]
+            .as_ref()
+            .map(|set| set.matched(*index))
+            .unwrap_or(false),
+        Node::Kind(FileKind::File) => matches!(entry.kind, FileKind::File | FileKind::Other),
+        Node::Kind(kind) => entry.kind == *kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, kind: FileKind) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            kind,
+            size: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            mode: 0,
+        }
+    }
+
+    fn is_match(source: &str, path: &str, kind: FileKind) -> bool {
+        let entry = entry(path, kind);
+        Expression::parse(source, CaseMode::Smart)
+            .unwrap()
+            .is_match(&entry, &entry.path)
+    }
+
+    #[test]
+    fn t_expression_evaluation() {
+        let source = "name:/test/ and ext:rs and not path:target";
+        assert!(is_match(source, "src/test_walker.rs", FileKind::File));
+        assert!(!is_match(source, "target/debug/test_walker.rs", FileKind::File));
+        assert!(!is_match(source, "src/test_walker.toml", FileKind::File));
+
+        let source = "(ext:rs or ext:toml) and not type:dir";
+        assert!(is_match(source, "Cargo.toml", FileKind::File));
+        assert!(!is_match(source, "weird.rs", FileKind::Dir));
+
+        // `and` binds tighter than `or`
+        assert!(is_match("main or lib and ext:md", "main.rs", FileKind::File));
+        assert!(is_match(r#"name:"my file" and path:/^docs\//"#, "docs/my file.txt", FileKind::File));
+        // smart case applies to every term on its own
+        assert!(is_match("name:README and ext:md", "README.MD", FileKind::File));
+        assert!(!is_match("name:README", "readme.md", FileKind::File));
+    }
+
+    #[test]
+    fn t_expression_errors() {
+        let message = |source: &str| Expression::parse(source, CaseMode::Smart).unwrap_err().to_string();
+
+        assert!(message("name:/test and ext:rs").contains("unclosed regex at column 6"));
+        assert!(message("size:10").contains("unknown field `size`"));
+        assert!(message("ext:rs and").contains("expected a term at column 11"));
+        assert!(message("(ext:rs or main").contains("unclosed `(` at column 1"));
+        assert!(message("ext: rs").contains("expected a value after `ext:`"));
+        assert!(message("main lib").contains("expected `and`, `or`"));
+        assert!(message("name:/(/").contains("Invalid expression"));
+    }
+}
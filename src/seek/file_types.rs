@@ -0,0 +1,81 @@
+//! Named sets of extensions, such as `rust` or `image`,
+//! given to `--type` instead of spelling out every extension
+
+// Use of the standard library
+use std::collections::HashMap;
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+
+/// The built-in types, the config can add more or override them
+const BUILTIN: &[(&str, &[&str])] = &[
+    ("archive", &["zip", "tar", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar"]),
+    ("audio", &["mp3", "wav", "flac", "ogg", "m4a", "aac"]),
+    ("c", &["c", "h"]),
+    ("config", &["toml", "yaml", "yml", "json", "ini", "conf"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh", "hxx", "h"]),
+    ("document", &["pdf", "doc", "docx", "odt", "rtf", "txt"]),
+    ("go", &["go"]),
+    ("image", &["png", "jpg", "jpeg", "gif", "bmp", "svg", "webp", "ico", "tiff"]),
+    ("java", &["java"]),
+    ("js", &["js", "mjs", "cjs", "jsx"]),
+    ("markdown", &["md", "markdown"]),
+    ("python", &["py", "pyi"]),
+    ("rust", &["rs"]),
+    ("shell", &["sh", "bash", "zsh", "fish"]),
+    ("ts", &["ts", "tsx", "mts", "cts"]),
+    ("video", &["mp4", "mkv", "webm", "avi", "mov"]),
+    ("web", &["html", "htm", "css", "scss"]),
+];
+
+/// Lowercases the extension and strips its leading period, if any
+pub fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+/// Every extension of the named types, where `custom` types take precedence
+pub fn resolve_types(names: &[String], custom: &HashMap<String, Vec<String>>) -> Result<Vec<String>> {
+    let mut extensions: Vec<String> = Vec::new();
+
+    for name in names {
+        let found: Vec<String> = match custom.get(name) {
+            Some(custom) => custom.clone(),
+            None => match BUILTIN.iter().find(|(builtin, _)| builtin == name) {
+                Some((_, builtin)) => builtin.iter().map(|e| e.to_string()).collect(),
+                None => {
+                    let mut known: Vec<&str> = BUILTIN.iter().map(|(name, _)| *name).collect();
+                    known.extend(custom.keys().map(|name| name.as_str()));
+                    known.sort();
+                    known.dedup();
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown type `{}`, expected one of: {}", name, known.join(", ")),
+                    ));
+                }
+            },
+        };
+        extensions.extend(found.iter().map(|e| normalize_extension(e)));
+    }
+
+    extensions.sort();
+    extensions.dedup();
+    Ok(extensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_resolve_types() {
+        let mut custom: HashMap<String, Vec<String>> = HashMap::new();
+        custom.insert("notes".to_string(), vec![".MD".to_string(), "org".to_string()]);
+        custom.insert("rust".to_string(), vec!["rs".to_string(), "ron".to_string()]);
+
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<String>>();
+        assert_eq!(resolve_types(&names(&["notes", "go"]), &custom).unwrap(), ["go", "md", "org"]);
+        assert_eq!(resolve_types(&names(&["rust"]), &custom).unwrap(), ["ron", "rs"]);
+
+        let error = resolve_types(&names(&["rustt"]), &custom).unwrap_err().to_string();
+        assert!(error.contains("Unknown type `rustt`") && error.contains("notes"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Definition of `SizeFilter`, `parse_size()` and `parse_time()`
+//!
+//! Filters on the metadata captured while scanning, so they
+//! apply to fresh scans and cached entries alike.
+
+// Importing from external crates
+use humantime::{parse_duration, parse_rfc3339_weak};
+use serde::{Deserialize, Serialize};
+
+// Use of the standard library
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A bound on the size of files, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeFilter {
+    /// At least, written as `+10M`
+    Min(u64),
+    /// At most, written as `-10M`
+    Max(u64),
+    /// Exactly, written as `10M`
+    Equals(u64),
+}
+
+impl SizeFilter {
+    /// Parses a bound such as `+10M`, `-512k` or `1.5GiB`, see `parse_size()`
+    pub fn parse(size: &str) -> Result<Self> {
+        let (constructor, amount): (fn(u64) -> Self, &str) = match size.strip_prefix('+') {
+            Some(amount) => (Self::Min, amount),
+            None => match size.strip_prefix('-') {
+                Some(amount) => (Self::Max, amount),
+                None => (Self::Equals, size),
+            },
+        };
+
+        match parse_size(amount) {
+            Ok(bytes) => Ok(constructor(bytes)),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid size `{}`: expected an amount and a unit such as `+10M`, `-512k` or `1GiB`",
+                    size
+                ),
+            )),
+        }
+    }
+
+    pub fn is_match(&self, size: u64) -> bool {
+        match *self {
+            Self::Min(min) => size >= min,
+            Self::Max(max) => size <= max,
+            Self::Equals(exact) => size == exact,
+        }
+    }
+}
+
+/// Parses a size such as `10M` or `1.5GiB` into bytes, units are case
+/// insensitive where `k`, `M`, `G` and `T` are powers of 1000
+/// and `Ki`, `Mi`, `Gi` and `Ti` are powers of 1024
+pub fn parse_size(size: &str) -> Result<u64> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid size `{}`: expected an amount and a unit such as `10M` or `1GiB`", size),
+        )
+    };
+
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+
+    let multiplier: u64 = match unit.to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "ki" | "kib" => 1 << 10,
+        "m" | "mb" => 1000u64.pow(2),
+        "mi" | "mib" => 1 << 20,
+        "g" | "gb" => 1000u64.pow(3),
+        "gi" | "gib" => 1 << 30,
+        "t" | "tb" => 1000u64.pow(4),
+        "ti" | "tib" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Seconds since the unix epoch, negative before it
+fn to_unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs() as i64),
+    }
+}
+
+/// Parses a point in time into seconds since the unix epoch, either a
+/// duration ago such as `2d` or `10min`, or a UTC date such as
+/// `2026-01-01` or `2026-01-01 12:30:00`
+pub fn parse_time(time: &str) -> Result<i64> {
+    if let Ok(ago) = parse_duration(time) {
+        let now = SystemTime::now();
+        return Ok(match now.checked_sub(ago) {
+            Some(then) => to_unix_seconds(then),
+            None => i64::MIN,
+        });
+    }
+
+    // a date alone is the start of the day
+    let date_time = if time.len() == "YYYY-MM-DD".len() {
+        format!("{} 00:00:00", time)
+    } else {
+        time.to_string()
+    };
+
+    match parse_rfc3339_weak(&date_time) {
+        Ok(then) => Ok(to_unix_seconds(then)),
+        Err(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid time `{}`: expected a duration such as `2d` or a date such as `2026-01-01`",
+                time
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_size_filter() {
+        assert_eq!(SizeFilter::parse("+10M").unwrap(), SizeFilter::Min(10_000_000));
+        assert_eq!(SizeFilter::parse("-1KiB").unwrap(), SizeFilter::Max(1024));
+        assert_eq!(SizeFilter::parse("1.5k").unwrap(), SizeFilter::Equals(1500));
+        assert_eq!(SizeFilter::parse("42").unwrap(), SizeFilter::Equals(42));
+        assert!(SizeFilter::parse("+10X").is_err());
+        assert!(SizeFilter::parse("M").is_err());
+        assert_eq!(parse_size("2Mi").unwrap(), 2 << 20);
+
+        assert!(SizeFilter::Min(100).is_match(100));
+        assert!(!SizeFilter::Max(100).is_match(101));
+    }
+
+    #[test]
+    fn t_parse_time() {
+        assert_eq!(parse_time("2026-01-01").unwrap(), 1_767_225_600);
+        assert_eq!(parse_time("2026-01-01 00:01:00").unwrap(), 1_767_225_660);
+        let now = to_unix_seconds(SystemTime::now());
+        assert!((now - 2 * 86_400 - parse_time("2d").unwrap()).abs() <= 1);
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Fuzzy matching, scoring how well a pattern matches a path
+//!
+//! Every character of the pattern must appear within the path, in order.
+//! Among all the ways the characters can be lined up, the best scoring
+//! one is found, much like fzf does:
+//!
+//! - every matched character scores, more so right after a path separator,
+//!   a word boundary such as `_`, `-` or `.`, or on a camelCase hump
+//! - consecutive matched characters earn a bonus, at least
+//!   the bonus of the first character of their run
+//! - characters skipped between matches cost a penalty
+//! - characters matched within the base name of the path score more
+
+/// Every matched character
+const SCORE_MATCH: i64 = 16;
+/// Starting a gap between matched characters
+const GAP_START: i64 = -3;
+/// Every further character of a gap
+const GAP_EXTENSION: i64 = -1;
+/// Matching right after a path separator
+const BONUS_SEPARATOR: i64 = 9;
+/// Matching right after a word boundary, or at the start
+const BONUS_BOUNDARY: i64 = 8;
+/// Matching an uppercase after a lowercase, or a digit after a non digit
+const BONUS_CAMEL: i64 = 7;
+/// Matching right after the previously matched character
+const BONUS_CONSECUTIVE: i64 = 4;
+/// The bonus of the first character of the pattern is multiplied
+const FIRST_CHAR_MULTIPLIER: i64 = 2;
+/// Matching within the base name rather than the parent directories
+const BONUS_BASE_NAME: i64 = 4;
+
+/// The bonus of matching the character at `index`
+fn bonus(text: &[char], index: usize) -> i64 {
+    let current = text[index];
+    let previous = match index.checked_sub(1) {
+        Some(previous) => text[previous],
+        None => return BONUS_BOUNDARY,
+    };
+
+    match previous {
+        '/' | '\\' => BONUS_SEPARATOR,
+        ' ' | '_' | '-' | '.' => BONUS_BOUNDARY,
+        _ if previous.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        _ if !previous.is_ascii_digit() && current.is_ascii_digit() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// Whether the pattern is a subsequence of the text, a cheap check ahead of scoring
+fn is_subsequence(pattern: &[char], text: &[char]) -> bool {
+    let mut remaining = pattern.iter().peekable();
+    for c in text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+/// Scores how well the pattern matches the text, `None` if it doesn't match at all
+pub fn score(pattern: &str, text: &str, case_sensitive: bool) -> Option<i64> {
+    // a single character per character, keeping both sides aligned
+    let normalize = |c: char| -> char {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    // the bonuses rely on the original case
+    let original: Vec<char> = text.chars().collect();
+    let text: Vec<char> = original.iter().copied().map(normalize).collect();
+
+    // where the base name starts
+    let base_name: usize = original
+        .iter()
+        .rposition(|c| *c == '/' || *c == '\\')
+        .map(|separator| separator + 1)
+        .unwrap_or(0);
+    let score_at = |j: usize| -> i64 {
+        if j >= base_name {
+            SCORE_MATCH + BONUS_BASE_NAME
+        } else {
+            SCORE_MATCH
+        }
+    };
+
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if !is_subsequence(&pattern, &text) {
+        return None;
+    }
+
+    // `previous[j]` is the best score with the last handled character
+    // of the pattern matched at `j`, `None` if it can't be matched there
+    let mut previous: Vec<Option<i64>> = (0..text.len())
+        .map(|j| {
+            (text[j] == pattern[0])
+                .then(|| score_at(j) + bonus(&original, j) * FIRST_CHAR_MULTIPLIER)
+        })
+        .collect();
+    // the bonus a consecutive match right after `j` carries on
+    let mut previous_run: Vec<i64> = (0..text.len()).map(|j| bonus(&original, j)).collect();
+
+    for &p in &pattern[1..] {
+        let mut current: Vec<Option<i64>> = vec![None; text.len()];
+        let mut current_run: Vec<i64> = vec![0; text.len()];
+        // the best score of a match before `j - 1`, gap penalty included
+        let mut gap: Option<i64> = None;
+
+        for j in 1..text.len() {
+            if j >= 2 {
+                gap = match (gap, previous[j - 2]) {
+                    (Some(gap), Some(start)) => Some((gap + GAP_EXTENSION).max(start + GAP_START)),
+                    (Some(gap), None) => Some(gap + GAP_EXTENSION),
+                    (None, start) => start.map(|start| start + GAP_START),
+                };
+            }
+
+            if text[j] != p {
+                continue;
+            }
+
+            let own = bonus(&original, j);
+            let run = previous_run[j - 1].max(BONUS_CONSECUTIVE).max(own);
+            let consecutive = previous[j - 1].map(|s| s + score_at(j) + run);
+            let gapped = gap.map(|s| s + score_at(j) + own);
+
+            (current[j], current_run[j]) = match (consecutive, gapped) {
+                (Some(a), Some(b)) if b > a => (Some(b), own),
+                (Some(a), _) => (Some(a), run),
+                (None, b) => (b, own),
+            };
+        }
+
+        previous = current;
+        previous_run = current_run;
+    }
+
+    previous.into_iter().flatten().max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_fuzzy_ranking() {
+        let candidates = [
+            "src/seek/scan_result.rs",
+            "src/regex_builder.rs",
+            "src/seek/search.rs",
+            "src/watch.rs",
+            "src/cache/cache.rs",
+            "README.md",
+        ];
+        let mut scored: Vec<(i64, &str)> = candidates
+            .iter()
+            .filter_map(|c| score("srchrs", c, false).map(|s| (s, *c)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+        assert_eq!(scored[0].1, "src/seek/search.rs");
+        assert!(score("srchrs", "README.md", false).is_none());
+    }
+
+    #[test]
+    fn t_fuzzy_bonuses() {
+        // consecutive characters beat scattered ones
+        assert!(score("main", "main.rs", false) > score("main", "m_a_i_n.rs", false));
+        // word boundaries and camelCase humps beat the middle of words
+        assert!(score("fb", "foo_bar", false) > score("fb", "fooba", false));
+        assert!(score("fb", "fooBar", false) > score("fb", "foobar", false));
+        assert!(score("Main", "main", true).is_none());
+        assert_eq!(score("", "anything", false), Some(0));
+    }
+}
@@ -0,0 +1,268 @@
+//! Definition of `IgnoreRules`
+//!
+//! Keeps track of the ignore files that apply to a directory
+//! while `scan()` walks it. Every directory owns its own rules
+//! and links back to the rules of its parent directory, so nested
+//! ignore files only apply to their own subtree.
+//!
+//! The following files are honored, in order of precedence:
+//!
+//! 1. `.seekignore`
+//! 2. `.ignore`
+//! 3. `.gitignore` (only inside of a git repository)
+//! 4. `.git/info/exclude` of the repository
+//! 5. The global git excludes file (`core.excludesFile`)
+
+// Importing from external crates
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+
+// standard library
+use std::path::absolute;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Ignore file names that are read from every directory
+const SEEK_IGNORE: &str = ".seekignore";
+const IGNORE: &str = ".ignore";
+const GIT_IGNORE: &str = ".gitignore";
+
+/// Values shared by every directory of a single scan
+#[derive(Debug)]
+struct Shared {
+    /// The root of the scan as given by the user
+    root: PathBuf,
+    /// The absolute form of `root`, used by the rules
+    /// read from the ancestors of the root
+    absolute_root: PathBuf,
+    /// The global git excludes file
+    global: Gitignore,
+}
+
+/// The rules read from a single directory
+#[derive(Debug)]
+struct Node {
+    parent: Option<IgnoreRules>,
+    seek: Option<Gitignore>,
+    ignore: Option<Gitignore>,
+    git: Option<Gitignore>,
+    /// `.git/info/exclude`, only present on repository roots
+    exclude: Option<Gitignore>,
+    /// Whether the directory contains a `.git` directory
+    is_repository: bool,
+    /// Whether the directory lives inside of a git repository
+    in_repository: bool,
+    /// Whether the directory is an ancestor of the scan root,
+    /// meaning its rules expect absolute paths
+    is_ancestor: bool,
+}
+
+/// The ignore rules that apply to a directory and its entries
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    node: Arc<Node>,
+    shared: Arc<Shared>,
+}
+
+/// Reads an ignore file if it exists within the directory
+fn read_ignore_file(dir: &Path, name: &str) -> Option<Gitignore> {
+    let file = dir.join(name);
+    if !file.is_file() {
+        return None;
+    }
+    // a partially invalid ignore file still returns the valid globs
+    let (matcher, _) = Gitignore::new(file);
+    if matcher.is_empty() {
+        return None;
+    }
+    Some(matcher)
+}
+
+/// Converts a match into a decision, `None` meaning undecided
+fn decide<T>(matched: Match<T>) -> Option<bool> {
+    match matched {
+        Match::None => None,
+        Match::Ignore(_) => Some(true),
+        Match::Whitelist(_) => Some(false),
+    }
+}
+
+impl Node {
+    fn new(dir: &Path, parent: Option<IgnoreRules>, is_ancestor: bool) -> Self {
+        let is_repository = dir.join(".git").exists();
+        let in_repository = is_repository
+            || parent
+                .as_ref()
+                .map(|p| p.node.in_repository)
+                .unwrap_or(false);
+
+        Self {
+            parent,
+            seek: read_ignore_file(dir, SEEK_IGNORE),
+            ignore: read_ignore_file(dir, IGNORE),
+            git: if in_repository {
+                read_ignore_file(dir, GIT_IGNORE)
+            } else {
+                None
+            },
+            exclude: if is_repository {
+                read_ignore_file(&dir.join(".git").join("info"), "exclude")
+            } else {
+                None
+            },
+            is_repository,
+            in_repository,
+            is_ancestor,
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Builds the rules for the scan root, reading the ignore
+    /// files of every ancestor of the root along the way
+    pub fn new(root: &Path) -> Self {
+        let absolute_root = absolute(root).unwrap_or(root.to_path_buf());
+        let shared = Arc::new(Shared {
+            root: root.to_path_buf(),
+            absolute_root: absolute_root.clone(),
+            global: Gitignore::global().0,
+        });
+
+        // walking from the top most ancestor down to the parent of the root
+        let mut ancestors: Vec<&Path> = absolute_root.ancestors().skip(1).collect();
+        ancestors.reverse();
+
+        let mut parent: Option<IgnoreRules> = None;
+        for ancestor in ancestors {
+            parent = Some(Self {
+                node: Arc::new(Node::new(ancestor, parent, true)),
+                shared: shared.clone(),
+            });
+        }
+
+        Self {
+            node: Arc::new(Node::new(root, parent, false)),
+            shared,
+        }
+    }
+
+    /// Returns the rules of a subdirectory
+    pub fn child(&self, dir: &Path) -> Self {
+        Self {
+            node: Arc::new(Node::new(dir, Some(self.clone()), false)),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Iterates from the current directory up through its ancestors
+    fn chain(&self) -> impl Iterator<Item = &Node> {
+        let mut current: Option<&IgnoreRules> = Some(self);
+        std::iter::from_fn(move || {
+            let rules = current?;
+            current = rules.node.parent.as_ref();
+            Some(rules.node.as_ref())
+        })
+    }
+
+    /// Checks whether an entry of the current directory is ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        // the rules of ancestors expect absolute paths
+        let absolute_path: PathBuf = match path.strip_prefix(&self.shared.root) {
+            Ok(relative) => self.shared.absolute_root.join(relative),
+            Err(_) => path.to_path_buf(),
+        };
+        let path_for = |node: &Node| -> &Path {
+            if node.is_ancestor {
+                &absolute_path
+            } else {
+                path
+            }
+        };
+
+        for node in self.chain() {
+            if let Some(seek) = &node.seek {
+                if let Some(ignored) = decide(seek.matched(path_for(node), is_dir)) {
+                    return ignored;
+                }
+            }
+        }
+
+        for node in self.chain() {
+            if let Some(ignore) = &node.ignore {
+                if let Some(ignored) = decide(ignore.matched(path_for(node), is_dir)) {
+                    return ignored;
+                }
+            }
+        }
+
+        if !self.node.in_repository {
+            return false;
+        }
+
+        let mut exclude: Option<(&Gitignore, &Node)> = None;
+        for node in self.chain() {
+            if let Some(git) = &node.git {
+                if let Some(ignored) = decide(git.matched(path_for(node), is_dir)) {
+                    return ignored;
+                }
+            }
+            if node.is_repository {
+                // rules of a parent repository don't apply to a nested one
+                exclude = node.exclude.as_ref().map(|e| (e, node));
+                break;
+            }
+        }
+
+        if let Some((exclude, node)) = exclude {
+            if let Some(ignored) = decide(exclude.matched(path_for(node), is_dir)) {
+                return ignored;
+            }
+        }
+
+        decide(self.shared.global.matched(path, is_dir)).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn t_nested_ignore_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join(".ignore"), "*.log\n").unwrap();
+        fs::write(root.join("sub").join(".seekignore"), "!keep.log\ntarget/\n").unwrap();
+
+        let rules = IgnoreRules::new(&root);
+        let sub = rules.child(&root.join("sub"));
+
+        assert!(rules.is_ignored(&root.join("a.log"), false));
+        assert!(!rules.is_ignored(&root.join("target"), true));
+        assert!(sub.is_ignored(&root.join("sub").join("b.log"), false));
+        assert!(!sub.is_ignored(&root.join("sub").join("keep.log"), false));
+        assert!(sub.is_ignored(&root.join("sub").join("target"), true));
+        assert!(!sub.is_ignored(&root.join("sub").join("target"), false));
+    }
+
+    #[test]
+    fn t_gitignore_requires_repository() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        let rules = IgnoreRules::new(&root);
+        assert!(!rules.is_ignored(&root.join("target"), true));
+
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "*.tmp\n").unwrap();
+
+        let rules = IgnoreRules::new(&root);
+        assert!(rules.is_ignored(&root.join("target"), true));
+        assert!(rules.is_ignored(&root.join("file.tmp"), false));
+    }
+}
@@ -0,0 +1,326 @@
+//! Incremental refresh of a previous scan
+//!
+//! Adding, removing or renaming an entry changes the modification
+//! time of its parent directory. A refresh walks the previous scan
+//! top down and only reads the directories whose modification time
+//! changed, the children of every other directory are reused as is.
+//! Only the subdirectories of reused directories are queried, so on
+//! a mostly static tree a refresh touches a fraction of the entries.
+//!
+//! Modifying a file in place doesn't change its directory, so the
+//! size and modification time of reused files may be outdated, the
+//! same goes for ignore files edited in place.
+
+// local functionality
+use crate::seek::Entry;
+use crate::seek::IgnoreRules;
+use crate::seek::ScanOptions;
+use crate::seek::ScanResult;
+
+use log::{info, warn};
+
+// standard library
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Ignore rules that are only read once a directory has to be read,
+/// reused directories never touch their ignore files
+struct PendingRules {
+    dir: PathBuf,
+    parent: Option<Rc<PendingRules>>,
+    rules: OnceCell<IgnoreRules>,
+}
+
+impl PendingRules {
+    fn new(dir: PathBuf, parent: Option<Rc<PendingRules>>) -> Rc<Self> {
+        Rc::new(Self {
+            dir,
+            parent,
+            rules: OnceCell::new(),
+        })
+    }
+
+    fn get(&self) -> &IgnoreRules {
+        self.rules.get_or_init(|| match &self.parent {
+            Some(parent) => parent.get().child(&self.dir),
+            None => IgnoreRules::new(&self.dir),
+        })
+    }
+}
+
+/// A directory waiting to be refreshed
+struct Job {
+    dir: PathBuf,
+    /// The depth of the directory relative to the root
+    depth: usize,
+    /// Whether the modification time matches the previous scan
+    unchanged: bool,
+    /// The ignore rules of the directory, if ignore files are honored
+    rules: Option<Rc<PendingRules>>,
+}
+
+/// The state of a single refresh
+struct Refresh<'r> {
+    root: &'r Path,
+    options: &'r ScanOptions,
+    /// The previous entries, grouped by their parent directory
+    children: HashMap<PathBuf, Vec<Entry>>,
+    jobs: Vec<Job>,
+    result: ScanResult,
+    /// The amount of directories that had to be read
+    read: usize,
+}
+
+impl<'r> Refresh<'r> {
+    fn warn(&mut self, message: String) {
+        self.result.increase_error(1);
+        if self.options.log {
+            warn!("{}", message);
+        }
+    }
+
+    fn queue(&mut self, entry: &Entry, depth: usize, unchanged: bool, rules: &Option<Rc<PendingRules>>) {
+        if !entry.is_dir() || depth > self.options.depth {
+            return;
+        }
+        self.jobs.push(Job {
+            dir: entry.path.clone(),
+            depth,
+            unchanged,
+            rules: rules
+                .as_ref()
+                .map(|parent| PendingRules::new(entry.path.clone(), Some(parent.clone()))),
+        });
+    }
+
+    fn run_job(&mut self, job: Job) {
+        // an empty directory has no previous children, yet is reused all the same
+        let previous: Vec<Entry> = self.children.remove(&job.dir).unwrap_or_default();
+        if job.unchanged {
+            self.reuse(&job, previous);
+        } else {
+            self.read(&job, previous);
+        }
+    }
+
+    /// Keeps the previous children, only querying the subdirectories
+    fn reuse(&mut self, job: &Job, previous: Vec<Entry>) {
+        let depth = job.depth + 1;
+
+        for old in previous {
+            if !old.is_dir() || depth > self.options.depth {
+                self.result.increase_success(1);
+                self.result.push(old);
+                continue;
+            }
+
+            let entry = match Entry::from_path(&old.path) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.warn(format!("{}: {}", old.path.display(), error));
+                    continue;
+                }
+            };
+
+            self.queue(&entry, depth, entry.same_mtime(&old), &job.rules);
+            self.result.increase_success(1);
+            self.result.push(entry);
+        }
+    }
+
+    /// Reads the directory again, like a regular scan does
+    fn read(&mut self, job: &Job, previous: Vec<Entry>) {
+        self.read += 1;
+        let entries = match fs::read_dir(&job.dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.warn(format!("{}: {}", job.dir.display(), error));
+                return;
+            }
+        };
+
+        let previous: HashMap<PathBuf, Entry> =
+            previous.into_iter().map(|e| (e.path.clone(), e)).collect();
+        let depth = job.depth + 1;
+
+        for entry in entries {
+            let entry = match entry.and_then(|entry| Entry::from_dir_entry(&entry)) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.warn(error.to_string());
+                    continue;
+                }
+            };
+
+            if self.options.exclusions.is_excluded(&entry.path, self.root) {
+                continue;
+            }
+
+            if let Some(rules) = &job.rules {
+                if rules.get().is_ignored(&entry.path, entry.is_dir()) {
+                    continue;
+                }
+            }
+
+            let unchanged = previous
+                .get(&entry.path)
+                .map(|old| old.is_dir() && old.same_mtime(&entry))
+                .unwrap_or(false);
+
+            self.queue(&entry, depth, unchanged, &job.rules);
+            self.result.increase_success(1);
+            self.result.push(entry);
+        }
+    }
+}
+
+/// Refreshes the entries of a previous scan of the root, made with the same options
+pub fn refresh(root: &Path, options: &ScanOptions, previous: Vec<Entry>) -> ScanResult {
+    run(root, options, previous).result
+}
+
+/// Runs every job of the refresh, starting from the root
+fn run<'r>(root: &'r Path, options: &'r ScanOptions, previous: Vec<Entry>) -> Refresh<'r> {
+    let mut children: HashMap<PathBuf, Vec<Entry>> = HashMap::new();
+    for entry in previous {
+        if let Some(parent) = entry.path.parent() {
+            children.entry(parent.to_path_buf()).or_default().push(entry);
+        }
+    }
+
+    let mut refresh = Refresh {
+        root,
+        options,
+        children,
+        jobs: Vec::new(),
+        result: ScanResult::new(),
+        read: 0,
+    };
+
+    // the root itself isn't part of the entries, so it's always read
+    refresh.jobs.push(Job {
+        dir: root.to_path_buf(),
+        depth: 0,
+        unchanged: false,
+        rules: if options.no_ignore {
+            None
+        } else {
+            Some(PendingRules::new(root.to_path_buf(), None))
+        },
+    });
+
+    let mut directories: usize = 0;
+    while let Some(job) = refresh.jobs.pop() {
+        directories += 1;
+        refresh.run_job(job);
+    }
+
+    if options.log {
+        info!("Read {} of {} directories", refresh.read, directories);
+    }
+
+    refresh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seek::walker::{walk, Sink};
+    use crate::seek::Exclusions;
+    use crate::seek::FileKind;
+    use tempfile::tempdir;
+
+    fn options() -> ScanOptions {
+        ScanOptions {
+            depth: usize::MAX,
+            log: false,
+            no_ignore: true,
+            threads: 1,
+            exclusions: Exclusions::new(&[], &[]).unwrap(),
+        }
+    }
+
+    fn paths(mut entries: Vec<Entry>) -> Vec<PathBuf> {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries.into_iter().map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn t_refresh_changed_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let still = root.join("still").join("deep");
+        let moving = root.join("moving");
+        fs::create_dir_all(&still).unwrap();
+        fs::create_dir_all(&moving).unwrap();
+        fs::create_dir_all(root.join("gone").join("inner")).unwrap();
+        fs::write(still.join("a.txt"), "").unwrap();
+        fs::write(moving.join("b.txt"), "").unwrap();
+
+        let mut previous = walk(&root, &options(), Sink::Collect).unwrap().entries;
+        // entries that only exist in the previous scan, telling
+        // apart the directories that were reused from the ones read
+        let ghost = |dir: &Path| Entry {
+            path: dir.join("ghost"),
+            kind: FileKind::File,
+            size: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            mode: 0,
+        };
+        previous.push(ghost(&still));
+        previous.push(ghost(&moving));
+
+        fs::write(moving.join("c.txt"), "").unwrap();
+        fs::remove_dir_all(root.join("gone")).unwrap();
+
+        let refreshed = paths(refresh(&root, &options(), previous).entries);
+        let mut expected = paths(walk(&root, &options(), Sink::Collect).unwrap().entries);
+        assert!(refreshed.contains(&moving.join("c.txt")));
+        assert!(!refreshed.contains(&root.join("gone")));
+        assert!(!refreshed.contains(&moving.join("ghost")));
+
+        // the unchanged directory was reused instead of read
+        expected.push(still.join("ghost"));
+        expected.sort();
+        assert_eq!(refreshed, expected);
+    }
+
+    #[test]
+    fn t_refresh_unchanged_empty_dirs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::create_dir_all(root.join("full").join("empty")).unwrap();
+        fs::write(root.join("full").join("a.txt"), "").unwrap();
+
+        let options = options();
+        let previous = walk(&root, &options, Sink::Collect).unwrap().entries;
+        let refreshed = run(&root, &options, previous.clone());
+
+        // only the root, which isn't part of the entries, is read again
+        assert_eq!(refreshed.read, 1);
+        assert_eq!(paths(refreshed.result.entries), paths(previous));
+    }
+
+    #[test]
+    fn t_refresh_depth() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("a").join("b").join("c")).unwrap();
+
+        // the directories within the root are walked `depth` levels deep, like a scan
+        let options = ScanOptions { depth: 1, ..options() };
+        let scanned = walk(&root, &options, Sink::Collect).unwrap().entries;
+        fs::write(root.join("a").join("b").join("new.txt"), "").unwrap();
+        fs::write(root.join("a").join("new.txt"), "").unwrap();
+
+        let refreshed = paths(refresh(&root, &options, scanned).entries);
+        assert_eq!(refreshed, paths(walk(&root, &options, Sink::Collect).unwrap().entries));
+        assert_eq!(refreshed, [root.join("a"), root.join("a").join("b"), root.join("a").join("new.txt")]);
+    }
+}
@@ -0,0 +1,396 @@
+//! Definition of `Matcher`
+//!
+//! The criteria a single path must meet to be considered a match,
+//! shared by `search()` on cached paths and by streamed scans.
+
+// importing from external crates
+use regex::bytes;
+use regex::Regex;
+
+// Importing local modules
+use crate::regex_builder::{build_glob, build_regex};
+use crate::seek::fuzzy;
+use crate::seek::Expression;
+use crate::seek::search::get_base_name;
+use crate::seek::Entry;
+use crate::seek::FileKind;
+use crate::seek::PathMode;
+use crate::seek::Query;
+use crate::seek::SizeFilter;
+use crate::seek::search::is_included;
+
+// Use of the standard library
+use std::borrow::Cow;
+use std::io::Result;
+use std::path::absolute;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// How the name of an entry is matched
+#[derive(Debug, Clone)]
+enum Pattern {
+    Regex(Regex),
+    /// A glob, matched on bytes
+    Glob(bytes::Regex),
+    /// A fuzzy pattern, applied on the path relative to the root by default
+    Fuzzy { pattern: String, case_sensitive: bool },
+    /// A boolean expression, carrying its own fields to match against
+    Expression(Expression),
+}
+
+/// The compiled query along with the object type
+/// and parent directory filters
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    pattern: Pattern,
+    /// The scanned root, paths are displayed and fuzzy matched relative to it
+    root: Option<PathBuf>,
+    /// The absolute form of `root`, which absolute paths are resolved against
+    absolute_root: Option<PathBuf>,
+    /// The form of the path the pattern is matched against, the base name if `None`
+    full_path: Option<PathMode>,
+    /// A regex the parent directory must match
+    path_regex: Option<Regex>,
+    dirs: bool,
+    files: bool,
+    symlinks: bool,
+    include: Vec<String>,
+    extensions: Vec<String>,
+    size: Vec<SizeFilter>,
+    newer: Option<i64>,
+    older: Option<i64>,
+}
+
+impl Matcher {
+    /// Initializes the Matcher
+    pub fn new(regex: Regex, dirs: bool, files: bool, symlinks: bool, include: &[String]) -> Self {
+        Self {
+            pattern: Pattern::Regex(regex),
+            root: None,
+            absolute_root: None,
+            full_path: None,
+            path_regex: None,
+            dirs,
+            files,
+            symlinks,
+            include: include.to_vec(),
+            extensions: Vec::new(),
+            size: Vec::new(),
+            newer: None,
+            older: None,
+        }
+    }
+
+    /// Compiles the query
+    pub fn from_query(query: &Query) -> Result<Self> {
+        let pattern = if query.expression {
+            Pattern::Expression(Expression::parse(&query.pattern, query.case)?)
+        } else if query.fuzzy {
+            Pattern::Fuzzy {
+                pattern: query.pattern.clone(),
+                case_sensitive: query.case.is_case_sensitive(&query.pattern),
+            }
+        } else if query.glob {
+            Pattern::Glob(build_glob(&query.pattern, query.case)?)
+        } else {
+            Pattern::Regex(build_regex(query.pattern.clone(), query.case, query.exact)?)
+        };
+
+        let path_regex = match &query.path_regex {
+            Some(path_regex) => Some(build_regex(path_regex.clone(), query.case, false)?),
+            None => None,
+        };
+
+        Ok(Self {
+            pattern,
+            root: None,
+            absolute_root: None,
+            // fuzzy patterns need more than the base name to go by
+            full_path: query
+                .full_path
+                .or(query.fuzzy.then_some(PathMode::Relative)),
+            path_regex,
+            dirs: query.dirs,
+            files: query.files,
+            symlinks: query.symlinks,
+            include: query.include.clone(),
+            extensions: query.extensions.clone(),
+            size: query.size.clone(),
+            newer: query.newer,
+            older: query.older,
+        })
+    }
+
+    /// Specifies the scanned root
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.root = Some(root.to_path_buf());
+        self.absolute_root = Some(absolute(root).unwrap_or(root.to_path_buf()));
+        self
+    }
+
+    /// Whether the matches are ranked by their score
+    pub fn is_ranked(&self) -> bool {
+        matches!(self.pattern, Pattern::Fuzzy { .. })
+    }
+
+    /// The path in the form given by `mode`
+    fn path_as<'p>(&'p self, path: &'p Path, mode: PathMode) -> Cow<'p, Path> {
+        match mode {
+            PathMode::Relative => Cow::Borrowed(
+                self.root
+                    .as_ref()
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(path),
+            ),
+            PathMode::Absolute => {
+                let relative = self.root.as_ref().and_then(|root| path.strip_prefix(root).ok());
+                match (&self.absolute_root, relative) {
+                    // joining an empty path would add a trailing separator
+                    (Some(absolute_root), Some(relative)) if relative.as_os_str().is_empty() => {
+                        Cow::Borrowed(absolute_root.as_path())
+                    }
+                    (Some(absolute_root), Some(relative)) => Cow::Owned(absolute_root.join(relative)),
+                    _ if path.is_absolute() => Cow::Borrowed(path),
+                    _ => match absolute(path) {
+                        Ok(absolute) => Cow::Owned(absolute),
+                        Err(_) => Cow::Borrowed(path),
+                    },
+                }
+            }
+        }
+    }
+
+    /// How well the entry matches the pattern, `None` if it doesn't.
+    /// Only fuzzy patterns tell matches apart, the rest score 0
+    pub fn score(&self, path: &Path) -> Option<i64> {
+        let subject: String = match self.full_path {
+            Some(mode) => self.path_as(path, mode).to_string_lossy().into_owned(),
+            None => get_base_name(path),
+        };
+
+        match &self.pattern {
+            Pattern::Regex(regex) => regex.is_match(&subject).then_some(0),
+            // globs only know of `/` as a separator
+            Pattern::Glob(regex) if cfg!(windows) => {
+                regex.is_match(subject.replace('\\', "/").as_bytes()).then_some(0)
+            }
+            Pattern::Glob(regex) => regex.is_match(subject.as_bytes()).then_some(0),
+            Pattern::Fuzzy { pattern, case_sensitive } => {
+                fuzzy::score(pattern, &subject, *case_sensitive)
+            }
+            // needs the whole entry, see `is_match`
+            Pattern::Expression(_) => Some(0),
+        }
+    }
+
+    /// Whether the parent directory matches the `path_regex`, if any
+    fn is_parent_match(&self, path: &Path) -> bool {
+        let path_regex = match &self.path_regex {
+            Some(path_regex) => path_regex,
+            None => return true,
+        };
+
+        let path = self.path_as(path, self.full_path.unwrap_or(PathMode::Relative));
+        let parent = path.parent().unwrap_or(Path::new(""));
+        path_regex.is_match(&parent.to_string_lossy())
+    }
+
+    /// Whether the name ends with one of the extensions, if any were given.
+    /// Compared against the end of the name, so `tar.gz` works as well
+    fn is_extension_match(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+
+        let name: String = get_base_name(path).to_lowercase();
+        self.extensions.iter().any(|extension| {
+            name.len() > extension.len() + 1
+                && name.ends_with(extension.as_str())
+                && name[..name.len() - extension.len()].ends_with('.')
+        })
+    }
+
+    /// Whether the size and the modification time are within bounds,
+    /// only files are considered when filtering by size
+    fn is_metadata_match(&self, entry: &Entry) -> bool {
+        if !self.size.is_empty() {
+            let is_file = matches!(entry.kind, FileKind::File | FileKind::Other);
+            if !is_file || !self.size.iter().all(|size| size.is_match(entry.size)) {
+                return false;
+            }
+        }
+
+        self.newer.map(|newer| entry.mtime >= newer).unwrap_or(true)
+            && self.older.map(|older| entry.mtime <= older).unwrap_or(true)
+    }
+
+    /// Pairs every match with its score, `None` unless ranked
+    pub fn scored(&self, matches: Vec<Entry>) -> Vec<(Entry, Option<i64>)> {
+        matches
+            .into_iter()
+            .map(|entry| match self.is_ranked() {
+                true => {
+                    let score: Option<i64> = self.score(&entry.path);
+                    (entry, score)
+                }
+                false => (entry, None),
+            })
+            .collect()
+    }
+
+    /// Sorts the scored matches, the best first, ties are broken by the
+    /// shortest path, then by the path. Unless ranked, the matches are
+    /// left as they are
+    pub fn rank(&self, scored: &mut [(Entry, Option<i64>)]) {
+        if !self.is_ranked() {
+            return;
+        }
+
+        scored.sort_by(|(x, a), (y, b)| {
+            b.unwrap_or(i64::MIN)
+                .cmp(&a.unwrap_or(i64::MIN))
+                .then_with(|| x.path.as_os_str().len().cmp(&y.path.as_os_str().len()))
+                .then_with(|| x.path.cmp(&y.path))
+        });
+    }
+
+    /// Checks whether the entry meets every criteria,
+    /// relying on the metadata captured while scanning
+    pub fn is_match(&self, entry: &Entry) -> bool {
+        if !(self.files == self.dirs && self.dirs == self.symlinks) {
+            // if all object types are the same, (true or false), that means
+            // no object type was specified or all types were specified, thus,
+            // only checking the types when they differ
+            let considered = match entry.kind {
+                FileKind::Dir => self.dirs,
+                FileKind::Symlink => self.symlinks,
+                FileKind::File | FileKind::Other => self.files,
+            };
+            if !considered {
+                return false;
+            }
+        }
+
+        if !self.is_metadata_match(entry) || !self.is_extension_match(&entry.path) {
+            return false;
+        }
+
+        let path = &entry.path;
+
+        let is_pattern_match = match &self.pattern {
+            Pattern::Expression(expression) => {
+                expression.is_match(entry, &self.path_as(path, PathMode::Relative))
+            }
+            _ => self.score(path).is_some(),
+        };
+        if !is_pattern_match || !self.is_parent_match(path) {
+            return false;
+        }
+
+        is_included(path, &self.include)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, kind: FileKind) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            kind,
+            size: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            mode: 0,
+        }
+    }
+
+    #[test]
+    fn t_type_filters_use_metadata() {
+        // none of these paths exist on disk
+        let dir = entry("./missing/src", FileKind::Dir);
+        let file = entry("./missing/src.rs", FileKind::File);
+        let link = entry("./missing/src.lnk", FileKind::Symlink);
+        let regex = Regex::new("src").unwrap();
+
+        let all = Matcher::new(regex.clone(), false, false, false, &[]);
+        assert!(all.is_match(&dir) && all.is_match(&file) && all.is_match(&link));
+
+        let dirs_and_links = Matcher::new(regex, true, false, true, &[]);
+        assert!(dirs_and_links.is_match(&dir));
+        assert!(!dirs_and_links.is_match(&file));
+        assert!(dirs_and_links.is_match(&link));
+    }
+
+    #[test]
+    fn t_full_path_and_path_regex() {
+        let nested = entry("./missing/src/seek/main.rs", FileKind::File);
+        let top = entry("./missing/main.rs", FileKind::File);
+        let query = Query {
+            pattern: r"^src/.*\.rs$".to_string(),
+            full_path: Some(PathMode::Relative),
+            ..Query::default()
+        };
+
+        let matcher = Matcher::from_query(&query).unwrap().with_root(Path::new("./missing"));
+        assert!(matcher.is_match(&nested));
+        assert!(!matcher.is_match(&top));
+
+        // the base name is still the target of the query
+        let query = Query {
+            pattern: "^main".to_string(),
+            path_regex: Some("seek$".to_string()),
+            ..Query::default()
+        };
+        let matcher = Matcher::from_query(&query).unwrap().with_root(Path::new("./missing"));
+        assert!(matcher.is_match(&nested));
+        assert!(!matcher.is_match(&top));
+    }
+
+    #[test]
+    fn t_absolute_paths_resolve_against_the_root() {
+        let matcher = Matcher::from_query(&Query::default()).unwrap().with_root(Path::new("./missing"));
+        let root: PathBuf = absolute("./missing").unwrap();
+
+        let path = matcher.path_as(Path::new("./missing/src/main.rs"), PathMode::Absolute);
+        assert_eq!(path, root.join("src/main.rs"));
+        assert_eq!(matcher.path_as(Path::new("./missing"), PathMode::Absolute), root);
+        assert_eq!(matcher.path_as(Path::new("/elsewhere"), PathMode::Absolute), Path::new("/elsewhere"));
+    }
+
+    #[test]
+    fn t_rank_breaks_ties_by_path() {
+        let query = Query {
+            pattern: "main".to_string(),
+            fuzzy: true,
+            ..Query::default()
+        };
+        let matcher = Matcher::from_query(&query).unwrap().with_root(Path::new("./missing"));
+        let matches = ["./missing/b/main.rs", "./missing/a/main.rs"].map(|path| entry(path, FileKind::File));
+
+        let mut scored = matcher.scored(matches.to_vec());
+        matcher.rank(&mut scored);
+        let ranked: Vec<PathBuf> = scored.into_iter().map(|(e, _)| e.path).collect();
+        assert_eq!(ranked, ["./missing/a/main.rs", "./missing/b/main.rs"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn t_extensions_compose() {
+        let query = Query {
+            pattern: "^main".to_string(),
+            extensions: vec!["rs".to_string(), "tar.gz".to_string()],
+            files: true,
+            ..Query::default()
+        };
+        let matcher = Matcher::from_query(&query).unwrap();
+
+        assert!(matcher.is_match(&entry("./missing/main.RS", FileKind::File)));
+        assert!(matcher.is_match(&entry("./missing/main.tar.gz", FileKind::File)));
+        assert!(!matcher.is_match(&entry("./missing/main.gz", FileKind::File)));
+        assert!(!matcher.is_match(&entry("./missing/main.rs", FileKind::Dir)));
+        assert!(!matcher.is_match(&entry("./missing/lib.rs", FileKind::File)));
+    }
+}
@@ -1,9 +1,47 @@
+mod contents;
+mod entry;
+mod exclusions;
+mod expression;
+mod file_types;
+mod filters;
+mod fuzzy;
+mod ignore_rules;
+mod incremental;
+mod matcher;
+mod query;
 mod scan;
+mod scan_options;
 mod scan_result;
 mod search;
+mod sort;
+mod walker;
 
+pub use contents::is_binary;
+pub use contents::search_contents;
+pub use contents::ContentMatch;
+pub use contents::ContentSearch;
+pub use entry::serialize_path;
+pub use entry::Entry;
+pub use entry::FileKind;
+pub use exclusions::Exclusions;
+pub use expression::Expression;
+pub use file_types::normalize_extension;
+pub use file_types::resolve_types;
+pub use filters::parse_size;
+pub use filters::parse_time;
+pub use filters::SizeFilter;
+pub use ignore_rules::IgnoreRules;
+pub use matcher::Matcher;
+pub use query::PathMode;
+pub use query::Query;
+pub use scan::refresh;
 pub use scan::scan;
+pub use scan::stream;
+pub use scan::ScanStream;
+pub use scan_options::ScanOptions;
 pub use scan_result::ScanResult;
 pub use search::filter_excluded_dirs;
 pub use search::filter_included_dirs;
 pub use search::search;
+pub use sort::sort_entries;
+pub use sort::SortKey;
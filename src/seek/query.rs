@@ -0,0 +1,54 @@
+//! Definition of `Query`
+//!
+//! What the user is seeking, kept apart from the compiled `Matcher`
+//! so it can be handed over to a running daemon as is.
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::regex_builder::CaseMode;
+use crate::seek::SizeFilter;
+
+/// Which form of the path a query is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PathMode {
+    /// The path relative to the starting path
+    Relative,
+    /// The absolute path
+    Absolute,
+}
+
+/// The criteria of a search, as given on the command line
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Query {
+    /// The regex applied on the base name of paths, unless specified otherwise
+    pub pattern: String,
+    /// Whether the pattern is a glob instead of a regex
+    pub glob: bool,
+    /// Whether the pattern is fuzzy matched against the path, ranking the matches
+    pub fuzzy: bool,
+    /// Whether the pattern is a boolean expression, see `Expression`
+    pub expression: bool,
+    /// Matches the pattern against the whole path instead of the base name
+    pub full_path: Option<PathMode>,
+    /// A regex the parent directory must match
+    pub path_regex: Option<String>,
+    /// How letters of different case are matched, smart by default
+    pub case: CaseMode,
+    /// Whether the pattern is matched as an exact string literal
+    pub exact: bool,
+    pub dirs: bool,
+    pub files: bool,
+    pub symlinks: bool,
+    /// Parent directory names that must be present within the path
+    pub include: Vec<String>,
+    /// Extensions the name must end with, lowercase without the leading period
+    pub extensions: Vec<String>,
+    /// Bounds every matching file's size must be within
+    pub size: Vec<SizeFilter>,
+    /// Modified at or after, in seconds since the unix epoch
+    pub newer: Option<i64>,
+    /// Modified at or before, in seconds since the unix epoch
+    pub older: Option<i64>,
+}
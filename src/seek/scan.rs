@@ -4,74 +4,64 @@
 //!
 //! `scan()` is an asynchronous function
 //!
-//! The function replicates threads for
-//! every subdirectory from the starting
-//! working directory, where each thread
-//! is responsible for it's own scanning.
+//! The function validates the starting path and
+//! hands it to the parallel walker, where a pool
+//! of threads share the pending directories of the
+//! whole tree, regardless of how deep they are.
 //!
-//! This behavior limits the amount of threads
-//! created while trying to create enough for
-//! the greatest performance.
+//! This behavior keeps every thread busy even
+//! when a single subdirectory holds most of the tree.
+//!
+//! `stream()` walks the same way, but hands over
+//! every entry through a bounded channel as soon as
+//! it's found instead of collecting the whole tree.
+//!
+//! `refresh()` updates a previous scan, only reading
+//! the directories that changed since, see `incremental`.
 
 // local functionality
+use crate::seek::incremental;
+use crate::seek::walker;
+use crate::seek::walker::Sink;
+use crate::seek::Entry;
+use crate::seek::ScanOptions;
 use crate::seek::ScanResult;
-use crate::utils;
 
 // Importing specific functions and structures from external crates
-use tokio::task::JoinHandle;
-use walkdir::WalkDir;
-use log::warn;
+use tokio::task::spawn_blocking;
 
 // standard library
-use std::fs;
 use std::io::Result;
 use std::io::{Error, ErrorKind};
 use std::path::Path;
-use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, Receiver};
 use std::thread;
 
-/// Iterates through the directories given recursively
-fn walk_all(dirs: &[PathBuf], depth: usize, log: bool) -> ScanResult {
-    let mut result = ScanResult::new();
+/// The amount of entries that can be waiting in the channel,
+/// bounding the memory of a stream regardless of the tree size
+const STREAM_CAPACITY: usize = 1024;
 
-    if depth == 0 {
-        return result;
-    }
+/// A scan in progress, delivering entries as they are found
+pub struct ScanStream {
+    pub receiver: Receiver<Entry>,
+    handle: thread::JoinHandle<Result<ScanResult>>,
+}
 
-    for dir in dirs.iter() {
-        let mut buffer = ScanResult::new();
-
-        for entry in WalkDir::new(dir).max_depth(depth) {
-            match entry {
-                Ok(entry) => {
-                    let entry = entry.path().to_path_buf();
-                    buffer.push(entry);
-                }
-
-                Err(error) => {
-                    buffer.increase_error(1);
-                    if log {
-                        // Not an error level log
-                        // since its not meant to
-                        // terminate or interrupt
-                        // the program
-                        warn!("{}", error.to_string());
-                    }
-                }
-            }
+impl ScanStream {
+    /// Waits for the walk to finish, returning its counters.
+    ///
+    /// If the receiver wasn't drained, the walk stops early
+    pub fn finish(self) -> Result<ScanResult> {
+        drop(self.receiver);
+        match self.handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(Error::new(ErrorKind::Other, "the scanning thread panicked")),
         }
-
-        // increasing the success counter at the end to decrease overhead
-        buffer.increase_success(buffer.size());
-        // merging the buffer into the main result structure
-        result.append(buffer);
     }
-
-    result
 }
 
-/// Scans all directories asynchronously keeping track of an error counter along the way
-pub async fn scan(path: &Path, depth: usize, log: bool) -> Result<ScanResult> {
+/// Errors if the path can't be scanned
+fn validate(path: &Path) -> Result<()> {
     if !path.exists() {
         // if path does not exist, error
         return Err(Error::new(
@@ -91,68 +81,47 @@ pub async fn scan(path: &Path, depth: usize, log: bool) -> Result<ScanResult> {
         ));
     }
 
-    //let mut collector = Vec::new();
-    //let mut success: usize = 0;
-    //let mut errors: usize = 0;
-    //
-    // Following struct encapsulates the previous logic
-    let mut result = ScanResult::new();
-
-    let initial_dirs: Vec<PathBuf> = {
-        let mut bind = Vec::new();
-
-        for entry in fs::read_dir(path)? {
-            match entry {
-                Ok(entry) => {
-                    result.increase_success(1);
-                    let entry = entry.path().to_path_buf();
-                    if entry.is_dir() {
-                        bind.push(entry.to_owned());
-                    } else {
-                        // not a directory, hence it doesn't need to be
-                        // in the initial_dirs to be walked
-                        result.push(entry.to_owned());
-                    }
-                }
-
-                Err(error) => {
-                    result.increase_error(1);
-                    if log {
-                        // not an error level log
-                        // since it's not meant to terminate
-                        // or interrupt the program
-                        warn!("{}", error.to_string());
-                    }
-                }
-            }
-        }
+    Ok(())
+}
 
-        bind
-    };
-
-    let cores_amount: usize = thread::available_parallelism()?.into();
-    // evenly distributing the workload per each core
-    let workload_per_core: Vec<Vec<PathBuf>> =
-        utils::distribute::<PathBuf>(&initial_dirs, cores_amount);
-    let mut workers: Vec<JoinHandle<ScanResult>> = Vec::new();
-
-    {
-        // initializing asynchronous threads
-        for workload in workload_per_core.iter() {
-            let w = workload.clone();
-            let worker: JoinHandle<ScanResult> =
-                tokio::spawn(async move { walk_all(&w, depth, log) });
-            workers.push(worker);
-        }
-    }
+/// Scans all directories asynchronously keeping track of an error counter along the way
+///
+/// Unless `options.no_ignore` is raised, entries matched by ignore files are left out.
+/// Excluded entries are left out as well, never descending into excluded directories
+pub async fn scan(path: &Path, options: &ScanOptions) -> Result<ScanResult> {
+    validate(path)?;
 
-    {
-        // awaiting all worker threads
-        for worker in workers {
-            let worker_result: ScanResult = worker.await?;
-            result.append(worker_result);
-        }
-    }
+    let root = path.to_path_buf();
+    let options = options.clone();
+    // the walker blocks while it owns its threads
+    let result: ScanResult = spawn_blocking(move || walker::walk(&root, &options, Sink::Collect)).await??;
 
     Ok(result)
 }
+
+/// Updates the entries of a previous scan made with the same options,
+/// reading only the directories that changed since
+pub async fn refresh(path: &Path, options: &ScanOptions, previous: Vec<Entry>) -> Result<ScanResult> {
+    validate(path)?;
+
+    let root = path.to_path_buf();
+    let options = options.clone();
+    let result: ScanResult =
+        spawn_blocking(move || incremental::refresh(&root, &options, previous)).await?;
+
+    Ok(result)
+}
+
+/// Scans all directories in the background, streaming every entry as it's found
+///
+/// The returned `ScanResult` of `ScanStream::finish()` only holds the counters
+pub fn stream(path: &Path, options: &ScanOptions) -> Result<ScanStream> {
+    validate(path)?;
+
+    let (sender, receiver) = sync_channel::<Entry>(STREAM_CAPACITY);
+    let root = path.to_path_buf();
+    let options = options.clone();
+    let handle = thread::spawn(move || walker::walk(&root, &options, Sink::Channel(sender)));
+
+    Ok(ScanStream { receiver, handle })
+}
@@ -0,0 +1,20 @@
+// Definition of `ScanOptions`
+//
+// Used to shape what the scan() function walks
+use crate::seek::Exclusions;
+
+/// A structure that encapsulates the options
+/// given to the scan() function
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// The recursion depth limit when walking directories
+    pub depth: usize,
+    /// Logs warnings of entries that couldn't be read
+    pub log: bool,
+    /// The amount of threads walking directories
+    pub threads: usize,
+    /// Disregards ignore files
+    pub no_ignore: bool,
+    /// Names and globs of entries to leave out
+    pub exclusions: Exclusions,
+}
@@ -1,16 +1,17 @@
 // Definition of `ScanResult`
 //
-// Used to keep track of file paths,
+// Used to keep track of scanned entries,
 // error counter, and success counter
+use crate::seek::Entry;
+
 use std::ops::Add;
-use std::path::PathBuf;
 
 /// A structure that encapsulates the scan()'s
-/// function result, containing the paths memoized
+/// function result, containing the entries memoized
 /// and the counters
 #[derive(Debug, Clone)]
 pub struct ScanResult {
-    pub paths: Vec<PathBuf>,
+    pub entries: Vec<Entry>,
     pub error_count: usize,
     pub success_count: usize,
 }
@@ -19,20 +20,20 @@ impl ScanResult {
     /// Initializes an empty ScanResult
     pub fn new() -> Self {
         ScanResult {
-            paths: Vec::new(),
+            entries: Vec::new(),
             error_count: 0,
             success_count: 0,
         }
     }
 
-    /// Pushes into self.paths
-    pub fn push(&mut self, path: PathBuf) {
-        self.paths.push(path);
+    /// Pushes into self.entries
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
     }
 
-    /// Returns the length of self.paths
+    /// Returns the length of self.entries
     pub fn size(&self) -> usize {
-        self.paths.len()
+        self.entries.len()
     }
 
     /// Increases the success counter
@@ -53,7 +54,7 @@ impl ScanResult {
 
     /// Merges another ScanResult into itself
     pub fn append(&mut self, mut other: ScanResult) {
-        self.paths.append(&mut other.paths);
+        self.entries.append(&mut other.entries);
         self.error_count += other.error_count;
         self.success_count += other.success_count;
     }
@@ -1,11 +1,13 @@
 //! Contains the `search()` implementation
 
 // importing from external crates
-use regex::Regex;
 use tokio::spawn;
 use tokio::task::JoinHandle;
 
 // Importing local modules
+use crate::seek::Entry;
+use crate::seek::Exclusions;
+use crate::seek::Matcher;
 use crate::utils;
 
 // Use of the standard library
@@ -16,180 +18,112 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::thread;
 
-fn get_base_name(path: &Path) -> String {
+pub fn get_base_name(path: &Path) -> String {
     path.file_name()
         .unwrap_or(OsStr::new(""))
-        .to_str()
-        .unwrap_or("")
-        .to_string()
+        .to_string_lossy()
+        .into_owned()
 }
 
 /// Helper function used in `search`
 ///
-/// Takes in a regex with a few arguments to give
-/// the best filtered result possible
-pub fn search_buffer(
-    paths: &[PathBuf],
-    reg: Regex,
-    dirs: bool,
-    files: bool,
-    symlinks: bool,
-) -> Vec<PathBuf> {
-    let mut matches: Vec<PathBuf> = Vec::new();
-
-    if files == dirs && dirs == symlinks {
-        // if all object types are the same, (true or false), that means
-        // no object type was specified or all types were specified, thus,
-        // the function should consider all types
-        for path in paths {
-            let base_name = get_base_name(path);
-
-            if reg.is_match(&base_name) {
-                //matches.push(path.display().to_string());
-                matches.push(path.to_owned());
-            }
-        }
-    } else {
-        for path in paths {
-            if !dirs && path.is_dir() {
-                continue;
-            }
-            if !files && path.is_file() {
-                continue;
-            }
-            if !symlinks && path.is_symlink() {
-                continue;
-            }
-
-            let base_name = get_base_name(path);
-
-            if reg.is_match(&base_name) {
-                matches.push(path.to_owned());
-            }
-        }
-    }
-
-    matches
+/// Keeps the entries that meet the criteria of the matcher
+pub fn search_buffer(entries: &[Entry], matcher: &Matcher) -> Vec<Entry> {
+    entries
+        .iter()
+        .filter(|entry| matcher.is_match(entry))
+        .cloned()
+        .collect()
 }
 
 /// Asynchronous searching for optimized performance
-pub async fn search(
-    paths: &[PathBuf],
-    reg: Regex,
-    dirs: bool,
-    files: bool,
-    symlinks: bool,
-) -> Result<Vec<PathBuf>> {
+pub async fn search(entries: &[Entry], matcher: &Matcher) -> Result<Vec<Entry>> {
     let cores_amount: usize = thread::available_parallelism()?.into();
-    let buffers: Vec<Vec<PathBuf>> = utils::distribute(paths, cores_amount);
-    let mut workers: Vec<JoinHandle<Vec<PathBuf>>> = Vec::new();
+    let buffers: Vec<Vec<Entry>> = utils::distribute(entries, cores_amount);
+    let mut workers: Vec<JoinHandle<Vec<Entry>>> = Vec::new();
 
     for buffer in buffers {
-        let regex = reg.clone();
-        let worker: JoinHandle<Vec<PathBuf>> =
-            spawn(async move { search_buffer(&buffer, regex, dirs, files, symlinks) });
+        let matcher = matcher.clone();
+        let worker: JoinHandle<Vec<Entry>> =
+            spawn(async move { search_buffer(&buffer, &matcher) });
         workers.push(worker);
     }
 
-    let mut found_result: Vec<PathBuf> = Vec::new();
+    let mut found_result: Vec<Entry> = Vec::new();
 
     for worker in workers {
-        let result: Vec<PathBuf> = worker.await?;
+        let result: Vec<Entry> = worker.await?;
         found_result.extend(result);
     }
 
     Ok(found_result)
 }
 
-/// Filters out found instance paths
-/// based if whether a parent directory
-/// was specified to be explicitly present
-/// given in the `excluded_names` parameter
-pub fn filter_included_dirs(result_paths: Vec<PathBuf>, included_names: &[String]) -> Vec<PathBuf> {
+/// Checks whether a parent directory of the path,
+/// or the path itself, is named after one of the `included_names`
+pub fn is_included(path: &Path, included_names: &[String]) -> bool {
     if included_names.is_empty() {
-        return result_paths;
+        return true;
     }
 
-    // initiating the vector with around the same capacity as the result paths
-    let mut filtered: Vec<PathBuf> = Vec::with_capacity(result_paths.len());
-
-    for path in result_paths.iter() {
-        let full_path: PathBuf = if path.is_absolute() {
-            path.to_owned()
+    let full_path: PathBuf = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        if let Ok(_full_path) = fs::canonicalize(path) {
+            _full_path
         } else {
-            if let Ok(_full_path) = fs::canonicalize(path) {
-                _full_path
-            } else {
-                // returning the original
-                // path if errors
-                path.to_owned()
-            }
-        };
-
-        // partitioning the path and checking the basenames
-
-        let mut is_included = false;
-
-        for ancestor in full_path.ancestors() {
-            let base_name = get_base_name(ancestor);
-
-            if included_names.contains(&base_name) {
-                is_included = true;
-                break;
-            }
+            // returning the original
+            // path if errors
+            path.to_owned()
         }
+    };
+
+    // partitioning the path and checking the basenames
+    for ancestor in full_path.ancestors() {
+        let base_name = get_base_name(ancestor);
 
-        if is_included {
-            filtered.push(path.to_owned());
+        if included_names.contains(&base_name) {
+            return true;
         }
     }
 
-    filtered
+    false
 }
 
 /// Filters out found instance paths
 /// based if whether a parent directory
-/// was specified to be explicitly *NOT* present
+/// was specified to be explicitly present
 /// given in the `excluded_names` parameter
-pub fn filter_excluded_dirs(result_paths: Vec<PathBuf>, excluded_names: &[String]) -> Vec<PathBuf> {
-    if excluded_names.is_empty() {
+pub fn filter_included_dirs<P: AsRef<Path>>(result_paths: Vec<P>, included_names: &[String]) -> Vec<P> {
+    if included_names.is_empty() {
         return result_paths;
     }
 
-    let mut filtered: Vec<PathBuf> = Vec::with_capacity(result_paths.len());
-
-    for path in result_paths.iter() {
-        let full_path: PathBuf = if path.is_absolute() {
-            path.to_owned()
-        } else {
-            if let Ok(_full_path) = fs::canonicalize(path) {
-                _full_path
-            } else {
-                // returning the original
-                // path if errors
-                path.to_owned()
-            }
-        };
-
-        // partitioning the path and checking the basenames
-
-        let mut is_excluded = false;
-
-        for ancestor in full_path.ancestors() {
-            let base_name = get_base_name(ancestor);
-
-            if excluded_names.contains(&base_name) {
-                is_excluded = true;
-                break;
-            }
-        }
+    result_paths
+        .into_iter()
+        .filter(|path| is_included(path.as_ref(), included_names))
+        .collect()
+}
 
-        if !is_excluded {
-            filtered.push(path.to_owned())
-        }
+/// Filters out found instance paths
+/// based if whether the path or a parent directory
+/// within the root is excluded by the given `exclusions`
+///
+/// Scans already prune excluded directories while walking,
+/// this is meant for paths that come from the cache
+pub fn filter_excluded_dirs<P: AsRef<Path>>(
+    result_paths: Vec<P>,
+    exclusions: &Exclusions,
+    root: &Path,
+) -> Vec<P> {
+    if exclusions.is_empty() {
+        return result_paths;
     }
 
-    filtered
+    result_paths
+        .into_iter()
+        .filter(|path| !exclusions.is_excluded_any(path.as_ref(), root))
+        .collect()
 }
 
 #[cfg(test)]
@@ -214,7 +148,8 @@ mod tests {
             PathBuf::from("./c/this/is/an/example/path/"),
         ];
 
-        let filtered_result = filter_excluded_dirs(found_paths, &exclusion_names);
+        let exclusions = Exclusions::new(exclusion_names, &[]).unwrap();
+        let filtered_result = filter_excluded_dirs(found_paths, &exclusions, Path::new("."));
         let result = vec![PathBuf::from("./b/this/is/an/example/path/")];
 
         assert_eq!(filtered_result, result);
@@ -0,0 +1,92 @@
+//! Definition of `SortKey`
+//!
+//! The order matches are listed in. Every key falls back on the
+//! path to break ties, so the order is the same between runs.
+
+// importing from external crates
+use clap::ValueEnum;
+
+// Importing local modules
+use crate::seek::Entry;
+
+// Use of the standard library
+use std::cmp::Ordering;
+
+/// What the matches are sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// The order they are found in, written out as soon as they are found
+    None,
+    /// The fuzzy score, the best first, see `Matcher::rank`
+    Score,
+    /// The base name
+    Name,
+    Path,
+    /// The size, the smallest first
+    Size,
+    /// The modification time, the oldest first
+    Mtime,
+    /// The amount of components of the path, the shallowest first
+    Depth,
+}
+
+fn compare(a: &Entry, b: &Entry, key: SortKey) -> Ordering {
+    let primary = match key {
+        SortKey::None | SortKey::Score | SortKey::Path => Ordering::Equal,
+        SortKey::Name => a.path.file_name().cmp(&b.path.file_name()),
+        SortKey::Size => a.size.cmp(&b.size),
+        SortKey::Mtime => (a.mtime, a.mtime_nsec).cmp(&(b.mtime, b.mtime_nsec)),
+        SortKey::Depth => a.path.components().count().cmp(&b.path.components().count()),
+    };
+    primary.then_with(|| a.path.cmp(&b.path))
+}
+
+/// Sorts the entries by the key, `None` and `Score` are left as they are
+pub fn sort_entries(entries: &mut [Entry], key: SortKey) {
+    if matches!(key, SortKey::None | SortKey::Score) {
+        return;
+    }
+    entries.sort_by(|a, b| compare(a, b, key));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seek::FileKind;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, size: u64, mtime: i64) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            kind: FileKind::File,
+            size,
+            mtime,
+            mtime_nsec: 0,
+            mode: 0,
+        }
+    }
+
+    #[test]
+    fn t_sort_entries() {
+        let mut entries = vec![
+            entry("./b/a.rs", 10, 3),
+            entry("./z.rs", 10, 1),
+            entry("./a/c.rs", 5, 2),
+        ];
+        let paths = |entries: &[Entry]| {
+            entries.iter().map(|e| e.path.to_string_lossy().into_owned()).collect::<Vec<String>>()
+        };
+
+        sort_entries(&mut entries, SortKey::Path);
+        assert_eq!(paths(&entries), ["./a/c.rs", "./b/a.rs", "./z.rs"]);
+        sort_entries(&mut entries, SortKey::Name);
+        assert_eq!(paths(&entries), ["./b/a.rs", "./a/c.rs", "./z.rs"]);
+        // ties are broken by the path
+        sort_entries(&mut entries, SortKey::Size);
+        assert_eq!(paths(&entries), ["./a/c.rs", "./b/a.rs", "./z.rs"]);
+        sort_entries(&mut entries, SortKey::Mtime);
+        assert_eq!(paths(&entries), ["./z.rs", "./a/c.rs", "./b/a.rs"]);
+        sort_entries(&mut entries, SortKey::Depth);
+        assert_eq!(paths(&entries), ["./z.rs", "./a/c.rs", "./b/a.rs"]);
+    }
+}
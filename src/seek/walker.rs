@@ -0,0 +1,349 @@
+//! Contains the parallel directory walker used by `scan()`
+//!
+//! Every directory is a job. Jobs are kept in per thread queues,
+//! a thread pushes the subdirectories it comes across onto its own
+//! queue and, once it runs out of work, steals pending directories
+//! from the queues of the other threads, at any depth of the tree.
+//!
+//! This way one huge subdirectory is shared among all threads instead
+//! of pinning a single thread while the rest sit idle.
+//!
+//! Entries are either collected into the `ScanResult` or sent over a
+//! channel as soon as they are found, see `Sink`.
+
+// local functionality
+use crate::seek::Entry;
+use crate::seek::IgnoreRules;
+use crate::seek::ScanOptions;
+use crate::seek::ScanResult;
+
+// Importing specific functions and structures from external crates
+use crossbeam_deque::{Injector, Stealer, Worker};
+use log::warn;
+
+// standard library
+use std::fs;
+use std::io::{Error, Result};
+use std::iter;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::Duration;
+
+/// The longest an idle thread sleeps before looking for a job again
+const MAX_IDLE_SLEEP: Duration = Duration::from_millis(1);
+
+/// Where the walker delivers the entries it comes across
+pub enum Sink {
+    /// Collects every entry into `ScanResult.entries`
+    Collect,
+    /// Sends every entry over the channel, the walk stops
+    /// early once the receiving end is dropped
+    Channel(SyncSender<Entry>),
+}
+
+/// A directory waiting to be read
+struct Job {
+    dir: PathBuf,
+    /// The depth of the directory relative to the root
+    depth: usize,
+    /// The ignore rules of the directory, if ignore files are honored
+    rules: Option<IgnoreRules>,
+}
+
+/// The state shared by all threads of a walk
+struct Walk<'w> {
+    root: &'w Path,
+    options: &'w ScanOptions,
+    sink: Sink,
+    /// Raised once the receiving end of the sink is gone
+    stopped: AtomicBool,
+    injector: Injector<Job>,
+    stealers: Vec<Stealer<Job>>,
+    /// Jobs that were queued but haven't finished yet
+    pending: AtomicUsize,
+}
+
+/// Marks a job as finished once dropped, even if it panicked,
+/// in which case the walk is stopped so no thread waits on it
+struct Finished<'w, 'a> {
+    walk: &'a Walk<'w>,
+}
+
+impl Drop for Finished<'_, '_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.walk.stopped.store(true, Ordering::SeqCst);
+        }
+        self.walk.pending.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<'w> Walk<'w> {
+    /// Takes a job from the local queue, otherwise steals one
+    fn find_job(&self, local: &Worker<Job>) -> Option<Job> {
+        local.pop().or_else(|| {
+            iter::repeat_with(|| {
+                self.injector
+                    .steal_batch_and_pop(local)
+                    .or_else(|| self.stealers.iter().map(|s| s.steal()).collect())
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(|steal| steal.success())
+        })
+    }
+
+    /// Reads a single directory, queueing its subdirectories
+    fn run_job(&self, job: Job, local: &Worker<Job>, result: &mut ScanResult) {
+        let entries = match fs::read_dir(&job.dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                result.increase_error(1);
+                if self.options.log {
+                    // not an error level log since it's not
+                    // meant to terminate or interrupt the program
+                    warn!("{}: {}", job.dir.display(), error);
+                }
+                return;
+            }
+        };
+
+        let depth = job.depth + 1;
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    result.increase_error(1);
+                    if self.options.log {
+                        warn!("{}", error);
+                    }
+                    continue;
+                }
+            };
+
+            // capturing the metadata now, symbolic links are not followed
+            let entry = match Entry::from_dir_entry(&entry) {
+                Ok(entry) => entry,
+                Err(error) => {
+                    result.increase_error(1);
+                    if self.options.log {
+                        warn!("{}", error);
+                    }
+                    continue;
+                }
+            };
+            let path = &entry.path;
+            let is_dir = entry.is_dir();
+
+            if self.options.exclusions.is_excluded(path, self.root) {
+                // excluded directories are never descended
+                continue;
+            }
+
+            if let Some(rules) = &job.rules {
+                if rules.is_ignored(path, is_dir) {
+                    // ignored directories are never descended
+                    continue;
+                }
+            }
+
+            result.increase_success(1);
+
+            // the directories within the root are walked `depth` levels deep
+            if is_dir && depth <= self.options.depth {
+                let rules = job.rules.as_ref().map(|r| r.child(path));
+                self.pending.fetch_add(1, Ordering::SeqCst);
+                local.push(Job {
+                    dir: path.clone(),
+                    depth,
+                    rules,
+                });
+            }
+
+            match &self.sink {
+                Sink::Collect => result.push(entry),
+                Sink::Channel(sender) => {
+                    if sender.send(entry).is_err() {
+                        // nobody is listening anymore
+                        self.stopped.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The loop of a single thread, runs until no job is pending
+    fn run(&self, local: Worker<Job>) -> ScanResult {
+        let mut result = ScanResult::new();
+        let mut idle = Duration::ZERO;
+
+        loop {
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match self.find_job(&local) {
+                Some(job) => {
+                    // children were queued before the job finishes, so
+                    // `pending` never reaches zero while there is work left
+                    let _finished = Finished { walk: self };
+                    self.run_job(job, &local, &mut result);
+                    idle = Duration::ZERO;
+                }
+                None => {
+                    if self.pending.load(Ordering::SeqCst) == 0 {
+                        break;
+                    }
+                    // backs off while the other threads are busy
+                    // with the directories left
+                    thread::sleep(idle);
+                    idle = (idle * 2).clamp(Duration::from_micros(1), MAX_IDLE_SLEEP);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Walks the root directory with `options.threads` threads,
+/// delivering the entries to the given sink.
+/// Errors if one of the threads panicked
+pub fn walk(root: &Path, options: &ScanOptions, sink: Sink) -> Result<ScanResult> {
+    let threads = options.threads.max(1);
+    let mut result = ScanResult::new();
+
+    let locals: Vec<Worker<Job>> = (0..threads).map(|_| Worker::new_lifo()).collect();
+    let walk = Walk {
+        root,
+        options,
+        sink,
+        stopped: AtomicBool::new(false),
+        injector: Injector::new(),
+        stealers: locals.iter().map(|w| w.stealer()).collect(),
+        pending: AtomicUsize::new(1),
+    };
+
+    walk.injector.push(Job {
+        dir: root.to_path_buf(),
+        depth: 0,
+        rules: if options.no_ignore {
+            None
+        } else {
+            Some(IgnoreRules::new(root))
+        },
+    });
+
+    let panicked: bool = thread::scope(|scope| {
+        let handles: Vec<_> = locals
+            .into_iter()
+            .map(|local| {
+                let walk = &walk;
+                scope.spawn(move || walk.run(local))
+            })
+            .collect();
+
+        let mut panicked: bool = false;
+        for handle in handles {
+            match handle.join() {
+                Ok(thread_result) => result.append(thread_result),
+                Err(_) => panicked = true,
+            }
+        }
+        panicked
+    });
+
+    if panicked {
+        return Err(Error::other("a scanning thread panicked"));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seek::Exclusions;
+    use tempfile::tempdir;
+
+    fn options(depth: usize) -> ScanOptions {
+        ScanOptions {
+            depth,
+            log: false,
+            no_ignore: true,
+            threads: 4,
+            exclusions: Exclusions::new(&[], &[]).unwrap(),
+        }
+    }
+
+    #[test]
+    fn t_walk_skewed_tree() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let deep = root.join("a").join("b").join("c");
+        fs::create_dir_all(&deep).unwrap();
+        fs::create_dir_all(root.join("d")).unwrap();
+        for i in 0..50 {
+            fs::write(deep.join(format!("{}.txt", i)), "").unwrap();
+        }
+
+        let entries = walk(&root, &options(usize::MAX), Sink::Collect).unwrap().entries;
+        assert_eq!(entries.len(), 54);
+        assert!(entries.iter().any(|e| e.path == deep.join("49.txt")));
+
+        // the directories within the root are walked `depth` levels deep
+        let entries = walk(&root, &options(1), Sink::Collect).unwrap().entries;
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().any(|e| e.path == root.join("a").join("b")));
+        let entries = walk(&root, &options(0), Sink::Collect).unwrap().entries;
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.is_dir()));
+    }
+
+    #[test]
+    fn t_walk_into_channel() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("a").join("b.txt"), "").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let result = thread::scope(|scope| {
+            let handle = scope.spawn(|| walk(&root, &options(usize::MAX), Sink::Channel(sender)));
+            assert_eq!(receiver.iter().count(), 2);
+            handle.join().unwrap().unwrap()
+        });
+        // sent entries are not collected
+        assert!(result.entries.is_empty());
+        assert_eq!(result.success_count, 2);
+    }
+
+    #[test]
+    fn t_panicked_job_stops_the_walk() {
+        let options = options(usize::MAX);
+        let walk = Walk {
+            root: Path::new("./missing"),
+            options: &options,
+            sink: Sink::Collect,
+            stopped: AtomicBool::new(false),
+            injector: Injector::new(),
+            stealers: Vec::new(),
+            pending: AtomicUsize::new(1),
+        };
+
+        let joined = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _finished = Finished { walk: &walk };
+                    panic!("a job panicked");
+                })
+                .join()
+        });
+        assert!(joined.is_err());
+        assert_eq!(walk.pending.load(Ordering::SeqCst), 0);
+        assert!(walk.stopped.load(Ordering::SeqCst));
+    }
+}
@@ -5,20 +5,23 @@
 use crate::options::Options;
 
 // Importing from external modules
-use chrono::prelude::*;
 use clipboard::{ClipboardContext, ClipboardProvider};
 
 // Using the standard library
+use std::borrow::Cow;
 use std::env::consts::OS;
+use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
-use std::fs::write;
-use std::fs::OpenOptions;
 use std::io::Result;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::io::{Error, ErrorKind};
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
-pub fn open_file(file: &str) -> Result<()> {
+pub fn open_file(file: &Path) -> Result<()> {
     #[cfg(target_os = "windows")]
     let mut cmd = Command::new("cmd")
         .arg("/C")
@@ -42,18 +45,6 @@ pub fn open_file(file: &str) -> Result<()> {
     Ok(())
 }
 
-/// An abstract function to write string content into a file
-/// giving the option to append to such file via a parameter
-pub fn write_to(loc: String, content: String, append: bool) -> Result<()> {
-    if append {
-        let mut file = OpenOptions::new().append(true).open(loc)?;
-        file.write_all(("\n".to_owned() + &content).as_bytes())?;
-        return Ok(());
-    }
-    write(loc, content)?;
-    Ok(())
-}
-
 /// Adds commas to a large number
 pub fn format_num(n: usize) -> String {
     // a vector instead of a string to avoid continuous dynamic sizing
@@ -80,12 +71,6 @@ pub fn distribute<T: Clone>(array: &[T], amount: usize) -> Vec<Vec<T>> {
     buffer.into_iter().filter(|buf| !buf.is_empty()).collect()
 }
 
-/// Returns todays numerical day in u8
-pub fn todays_day() -> u8 {
-    let local = Local::now();
-    local.day() as u8
-}
-
 /// Returns a pretty interface like list for the user to view
 pub fn pretty_interface(data: &[String], enumerate: bool) -> String {
     let mut buffer: Vec<String> = Vec::new();
@@ -117,9 +102,9 @@ pub fn copy(value: String) {
         .expect("Could not copy contents into the clipboard");
 }
 
-/// Prompts the user to a UI where the user
-/// selects the file path he sought for
-pub fn user_select(matches: &[String]) -> Option<String> {
+/// Prompts the user to select some of the matches,
+/// returning their indices, `None` when nothing was entered
+pub fn user_select(matches: &[String]) -> Option<Vec<usize>> {
     let options = Options::new(matches);
 
     loop {
@@ -137,15 +122,169 @@ pub fn user_select(matches: &[String]) -> Option<String> {
     }
 }
 
-pub fn interpolate_to_command(cmd: String, path: &str) -> String {
-    return cmd.replace("{}", path);
+pub fn interpolate_to_command(cmd: String, path: &str) -> OsString {
+    interpolate(&cmd, &[Target { path: Path::new(path), line: None }], Path::new(""))
+}
+
+/// A path interpolated into a command, along with its matching line
+pub struct Target<'t> {
+    pub path: &'t Path,
+    pub line: Option<usize>,
+}
+
+/// The parent directory of the path, `.` for a bare name
+pub fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// The bytes of the value, as they are on unix
+#[cfg(unix)]
+pub fn os_bytes(value: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(value.as_bytes())
+}
+
+/// The bytes of the value, lossily converted to UTF-8
+#[cfg(not(unix))]
+pub fn os_bytes(value: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Owned(value.to_string_lossy().into_owned().into_bytes())
+}
+
+/// The value of the placeholder named between the braces, `None` if it isn't one
+fn placeholder(name: &str, target: &Target, root: &Path) -> Option<OsString> {
+    let path: &Path = target.path;
+    let or_path = |part: Option<&OsStr>| part.unwrap_or(path.as_os_str()).to_os_string();
+
+    let value: OsString = match name {
+        "" => path.as_os_str().to_os_string(),
+        "/" => or_path(path.file_name()),
+        "//" => parent_dir(path).into_os_string(),
+        "." => path.with_extension("").into_os_string(),
+        "/." => or_path(path.file_stem()),
+        "ext" => path.extension().map(OsStr::to_os_string).unwrap_or_default(),
+        "root" => match path.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => OsString::from("."),
+            Ok(relative) => relative.as_os_str().to_os_string(),
+            Err(_) => path.as_os_str().to_os_string(),
+        },
+        // the first line when there is none
+        "line" => OsString::from(target.line.unwrap_or(1).to_string()),
+        _ => return None,
+    };
+    Some(value)
 }
 
+/// Characters `cmd` gives a meaning to, even within double quotes for `%` and `!`
 #[cfg(target_os = "windows")]
-pub fn run_cmd(cmd_query: String) -> Result<()> {
+const CMD_SPECIAL: [char; 9] = ['&', '^', '|', '<', '>', '(', ')', '%', '!'];
+
+/// Quotes the value so the shell reads it as a single argument.
+///
+/// `cmd` expands `%VAR%` and `!VAR!` within double quotes as well, so
+/// every special character is escaped by a `^` instead, the quotes included
+/// so `cmd` doesn't stop reading the escapes. The quotes are left for the
+/// command itself to group the value as a single argument
+#[cfg(target_os = "windows")]
+pub fn quote(value: &OsStr) -> OsString {
+    let value: Cow<str> = value.to_string_lossy();
+    if !value.is_empty() && !value.contains(' ') && !value.contains(CMD_SPECIAL) {
+        return OsString::from(value.as_ref());
+    }
+
+    // paths can't hold double quotes on Windows
+    let mut quoted = String::from("^\"");
+    for c in value.chars() {
+        if CMD_SPECIAL.contains(&c) {
+            quoted.push('^');
+        }
+        quoted.push(c);
+    }
+    // a trailing `\` would otherwise escape the closing quote
+    if value.ends_with('\\') {
+        quoted.push('\\');
+    }
+    quoted.push_str("^\"");
+    OsString::from(quoted)
+}
+
+/// Quotes the value so the shell reads it as a single argument,
+/// the bytes of values that aren't valid UTF-8 are kept as they are
+#[cfg(not(target_os = "windows"))]
+pub fn quote(value: &OsStr) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    if let Some(value) = value.to_str() {
+        return OsString::from(shell_words::quote(value).as_ref());
+    }
+
+    // within single quotes, every byte but the quote itself is taken literally
+    let mut quoted: Vec<u8> = vec![b'\''];
+    for byte in os_bytes(value).iter() {
+        match byte {
+            b'\'' => quoted.extend_from_slice(b"'\\''"),
+            byte => quoted.push(*byte),
+        }
+    }
+    quoted.push(b'\'');
+    OsString::from_vec(quoted)
+}
+
+/// Replaces the placeholders of the command by the values of every target,
+/// each quoted for the shell and separated by spaces. `root` is the path
+/// `{root}` is relative to. Unknown placeholders are left as they are
+pub fn interpolate(cmd: &str, targets: &[Target], root: &Path) -> OsString {
+    let mut interpolated = OsString::with_capacity(cmd.len());
+    let mut rest: &str = cmd;
+
+    while let Some(start) = rest.find('{') {
+        interpolated.push(&rest[..start]);
+        rest = &rest[start..];
+
+        let values: Option<(usize, Vec<OsString>)> = rest.find('}').and_then(|end| {
+            let name: &str = &rest[1..end];
+            let values: Option<Vec<OsString>> = targets.iter().map(|target| placeholder(name, target, root)).collect();
+            values.map(|values| (end, values))
+        });
+        match values {
+            Some((end, values)) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        interpolated.push(" ");
+                    }
+                    interpolated.push(quote(value));
+                }
+                rest = &rest[end + 1..];
+            }
+            None => {
+                interpolated.push("{");
+                rest = &rest[1..];
+            }
+        }
+    }
+    interpolated.push(rest);
+    interpolated
+}
+
+/// Cuts the text down to `max` characters, marking that it was cut
+pub fn shorten(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn run_cmd(cmd_query: OsString) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    // passed as it is, the escapes are meant for `cmd` itself
     let mut cmd = Command::new("cmd")
         .arg("/C")
-        .arg(cmd_query)
+        .raw_arg(cmd_query)
         .spawn()?;
 
     cmd.wait()?;
@@ -154,7 +293,7 @@ pub fn run_cmd(cmd_query: String) -> Result<()> {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn run_cmd(cmd_query: String) -> Result<()> {
+pub fn run_cmd(cmd_query: OsString) -> Result<()> {
     let mut cmd = Command::new("sh")
         .arg("-c")
         .arg(cmd_query)
@@ -165,6 +304,60 @@ pub fn run_cmd(cmd_query: String) -> Result<()> {
     return Ok(())
 }
 
+/// Runs the command, returning what it wrote onto the standard output.
+/// The command is killed once it runs for longer than `timeout`
+#[cfg(target_os = "windows")]
+pub fn cmd_output(cmd_query: OsString, timeout: Duration) -> Result<String> {
+    use std::os::windows::process::CommandExt;
+
+    output_within(Command::new("cmd").arg("/C").raw_arg(cmd_query), timeout)
+}
+
+/// Runs the command, returning what it wrote onto the standard output.
+/// The command is killed once it runs for longer than `timeout`
+#[cfg(not(target_os = "windows"))]
+pub fn cmd_output(cmd_query: OsString, timeout: Duration) -> Result<String> {
+    output_within(Command::new("sh").arg("-c").arg(cmd_query), timeout)
+}
+
+fn output_within(command: &mut Command, timeout: Duration) -> Result<String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let Some(mut stdout) = child.stdout.take() else {
+        return Err(Error::other("the output of the command could not be read"));
+    };
+
+    // read on its own thread, the output may never end
+    // while anything the command started holds onto it
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        let mut output: Vec<u8> = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = sender.send(output);
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(output) => {
+            child.wait()?;
+            Ok(String::from_utf8_lossy(&output).into_owned())
+        }
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "the command didn't finish within {}",
+                    humantime::format_duration(timeout)
+                ),
+            ))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,10 +367,75 @@ mod tests {
     fn t_interpolation() {
         let cmd = "type {} | clip".to_string();
         let path = "./hello.go";
-        let expected_result = "type ./hello.go | clip".to_string();
+        let expected_result = OsString::from("type ./hello.go | clip");
         assert_eq!(interpolate_to_command(cmd, path), expected_result);
     }
 
+    #[test]
+    fn t_line_interpolation() {
+        let target = Target {
+            path: Path::new("./main.rs"),
+            line: Some(12),
+        };
+        assert_eq!(interpolate("vim +{line} {}", &[target], Path::new(".")), "vim +12 ./main.rs");
+        assert_eq!(interpolate_to_command("vim +{line} {}".to_string(), "a.rs"), "vim +1 a.rs");
+        assert_eq!(shorten("abcdef", 3), "abc...");
+    }
+
+    #[test]
+    // quoting differs on Windows
+    #[cfg(not(target_os = "windows"))]
+    fn t_placeholders() {
+        let target = |path| Target { path: Path::new(path), line: None };
+        let root = Path::new("/home/me");
+        let cmd = "{} {/} {//} {.} {/.} {ext} {root} {x}";
+
+        assert_eq!(
+            interpolate(cmd, &[target("/home/me/src/main.rs")], root),
+            "/home/me/src/main.rs main.rs /home/me/src /home/me/src/main main rs src/main.rs {x}"
+        );
+        assert_eq!(interpolate(cmd, &[target("Makefile")], root), "Makefile Makefile . Makefile Makefile '' Makefile {x}");
+
+        // values are quoted, they can't break out of the command
+        assert_eq!(
+            interpolate("cat {} {/.}", &[target("./it's; rm -rf ~.txt")], root),
+            r#"cat './it'\''s; rm -rf ~.txt' 'it'\''s; rm -rf ~'"#
+        );
+        assert_eq!(interpolate("mv {} {{}", &[target("a b"), target("c")], root), "mv 'a b' c {'a b' c");
+
+        // paths that aren't valid UTF-8 are passed on byte for byte
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(OsStr::from_bytes(b"./it's caf\xe9.txt"));
+        let interpolated = interpolate("cat {}", &[Target { path, line: None }], root);
+        assert_eq!(interpolated.as_bytes(), b"cat './it'\\''s caf\xe9.txt'");
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn t_placeholders() {
+        let target = |path| Target { path: Path::new(path), line: None };
+        let root = Path::new(r"C:\Users\me");
+
+        assert_eq!(interpolate("type {}", &[target(r"C:\a.txt")], root), r"type C:\a.txt");
+        // neither `%` nor `!` are expanded, and nothing breaks out of the command
+        assert_eq!(
+            interpolate("type {}", &[target(r"C:\100% & !PATH!.txt")], root),
+            r#"type ^"C:\100^% ^& ^!PATH^!.txt^""#
+        );
+        assert_eq!(interpolate("dir {}", &[target(r"C:\my dir\")], root), r#"dir ^"C:\my dir\\^""#);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn t_cmd_output_timeout() {
+        let timeout = Duration::from_secs(5);
+        assert_eq!(cmd_output("echo seek".into(), timeout).unwrap(), "seek\n");
+
+        let error = cmd_output("sleep 10".into(), Duration::from_millis(100)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "the command didn't finish within 100ms");
+    }
+
     #[test]
     fn t_format_number_1000() {
         assert_eq!(format_num(1000), "1,000");
@@ -0,0 +1,350 @@
+//! Contains the `watch()` definition and implementation
+//!
+//! Keeps the cache in sync with the scanned root. The entries of the
+//! cache are held in an `Index`, the filesystem events of the root are
+//! applied onto it as they arrive and, every so often, the index is
+//! written back into the cache when anything changed.
+//!
+//! An event only tells which path changed, so the path is looked up
+//! again, if it still exists it's added or updated, otherwise it's
+//! removed along with everything beneath it.
+
+// Importing from internal modules
+use crate::cache::Cache;
+use crate::cache::Data;
+use crate::cache::Fingerprint;
+use crate::seek::scan;
+use crate::seek::Entry;
+use crate::seek::IgnoreRules;
+use crate::seek::ScanOptions;
+
+// Importing from external modules
+use log::{info, warn};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc::unbounded_channel;
+
+// Using the standard library
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Result;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+/// The cache is written at least this often even when nothing
+/// changed, so it never outlives its TTL while being watched
+const KEEP_ALIVE: Duration = Duration::from_secs(60 * 60);
+
+/// Files that change which entries are ignored
+const IGNORE_FILES: [&str; 4] = [".gitignore", ".ignore", ".seekignore", "exclude"];
+
+/// The entries of the cache, kept sorted by path so
+/// that everything beneath a directory is contiguous
+pub struct Index {
+    root: PathBuf,
+    options: ScanOptions,
+    entries: BTreeMap<PathBuf, Entry>,
+    /// The ignore rules of every directory that was looked at
+    rules: HashMap<PathBuf, IgnoreRules>,
+    /// Whether anything changed since the last write
+    dirty: bool,
+    /// Raised when an ignore file changed or events were
+    /// lost, only a new scan can tell what changed
+    rescan: bool,
+}
+
+impl Index {
+    pub fn new(root: &Path, options: &ScanOptions, entries: Vec<Entry>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            options: options.clone(),
+            entries: entries.into_iter().map(|e| (e.path.clone(), e)).collect(),
+            rules: HashMap::new(),
+            dirty: false,
+            rescan: false,
+        }
+    }
+
+    /// The depth of a path relative to the root
+    fn depth_of(&self, path: &Path) -> Option<usize> {
+        path.strip_prefix(&self.root)
+            .ok()
+            .map(|relative| relative.components().count())
+    }
+
+    /// The ignore rules of a directory, built from the root down
+    fn rules_of(&mut self, dir: &Path) -> IgnoreRules {
+        if let Some(rules) = self.rules.get(dir) {
+            return rules.clone();
+        }
+
+        let rules = match dir.parent() {
+            Some(parent) if dir != self.root => self.rules_of(parent).child(dir),
+            _ => IgnoreRules::new(dir),
+        };
+        self.rules.insert(dir.to_path_buf(), rules.clone());
+        rules
+    }
+
+    /// Whether a scan would have come across the path
+    fn accepts(&mut self, path: &Path, is_dir: bool) -> bool {
+        let depth = match self.depth_of(path) {
+            // the directories within the root are walked `depth` levels deep
+            Some(depth) if depth > 0 && depth - 1 <= self.options.depth => depth,
+            _ => return false,
+        };
+        let parent = match path.parent() {
+            Some(parent) => parent,
+            None => return false,
+        };
+
+        // a parent that isn't indexed was either ignored, excluded
+        // or too deep, so were all of its descendants
+        if depth > 1 && !self.entries.get(parent).map(|e| e.is_dir()).unwrap_or(false) {
+            return false;
+        }
+
+        if self.options.exclusions.is_excluded(path, &self.root) {
+            return false;
+        }
+
+        self.options.no_ignore || !self.rules_of(parent).is_ignored(path, is_dir)
+    }
+
+    /// Adds or updates the path, a new directory is read along with all of its descendants
+    pub fn add(&mut self, path: &Path) {
+        let entry = match Entry::from_path(path) {
+            Ok(entry) => entry,
+            Err(_) => return self.remove(path),
+        };
+
+        if !self.accepts(path, entry.is_dir()) {
+            return;
+        }
+
+        let is_new_dir = entry.is_dir()
+            && !self.entries.get(path).map(|e| e.is_dir()).unwrap_or(false);
+        self.entries.insert(path.to_path_buf(), entry);
+        self.dirty = true;
+
+        if !is_new_dir || self.depth_of(path).unwrap_or(0) > self.options.depth {
+            return;
+        }
+
+        if let Ok(children) = fs::read_dir(path) {
+            for child in children.flatten() {
+                self.add(&child.path());
+            }
+        }
+    }
+
+    /// Removes the path along with everything beneath it
+    pub fn remove(&mut self, path: &Path) {
+        let removed: Vec<PathBuf> = self
+            .entries
+            .range(path.to_path_buf()..)
+            .map(|(p, _)| p)
+            .take_while(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+
+        self.rules.retain(|dir, _| !dir.starts_with(path));
+        for path in removed {
+            self.entries.remove(&path);
+            self.dirty = true;
+        }
+    }
+
+    /// Brings the paths of an event up to date
+    pub fn apply(&mut self, event: &Event) {
+        if event.need_rescan() {
+            self.rescan = true;
+            return;
+        }
+
+        if matches!(event.kind, EventKind::Access(_) | EventKind::Other) {
+            return;
+        }
+
+        for path in event.paths.iter() {
+            let is_ignore_file = path
+                .file_name()
+                .map(|name| IGNORE_FILES.iter().any(|file| name == *file))
+                .unwrap_or(false);
+            if is_ignore_file && !self.options.no_ignore {
+                self.rescan = true;
+            }
+
+            if path.exists() || path.is_symlink() {
+                self.add(path);
+            } else {
+                self.remove(path);
+            }
+        }
+    }
+
+    /// Replaces every entry with the ones of a new scan
+    pub fn reset(&mut self, entries: Vec<Entry>) {
+        let root = self.root.clone();
+        let options = self.options.clone();
+        *self = Self::new(&root, &options, entries);
+        self.dirty = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// Turns the index into the data written into the cache
+    pub fn to_data(&self, fingerprint: &Fingerprint) -> Data {
+        Data::from(self.entries.values().cloned().collect::<Vec<Entry>>())
+            .with_fingerprint(fingerprint.clone())
+    }
+}
+
+fn watch_error(error: notify::Error) -> Error {
+    Error::new(ErrorKind::Other, format!("Could not watch for changes: {}", error))
+}
+
+/// Locks the index for reading, a panic while it was
+/// locked doesn't leave it any less consistent
+pub fn read(index: &RwLock<Index>) -> RwLockReadGuard<'_, Index> {
+    index.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write(index: &RwLock<Index>) -> RwLockWriteGuard<'_, Index> {
+    index.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Watches the root until interrupted, writing the changes into the cache
+/// every `interval`. The data must be up to date with the root
+pub async fn watch(
+    root: &Path,
+    options: &ScanOptions,
+    cache: &Cache,
+    data: Data,
+    interval: Duration,
+) -> Result<()> {
+    let fingerprint: Fingerprint = data.fingerprint.clone();
+    let index = RwLock::new(Index::new(root, options, data.data));
+    keep_in_sync(root, options, cache, &index, &fingerprint, interval).await
+}
+
+/// Applies the changes of the root onto the index until interrupted,
+/// writing the index into the cache every `interval`. A rescan that
+/// failed is tried again on the next tick, the index is kept meanwhile
+pub async fn keep_in_sync(
+    root: &Path,
+    options: &ScanOptions,
+    cache: &Cache,
+    index: &RwLock<Index>,
+    fingerprint: &Fingerprint,
+    interval: Duration,
+) -> Result<()> {
+    let (sender, mut receiver) = unbounded_channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // the receiver is only gone once watching stopped
+        let _ = sender.send(event);
+    })
+    .map_err(watch_error)?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(watch_error)?;
+
+    println!(
+        "Watching `{}` for changes, press Ctrl-C to stop",
+        root.display()
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+    let mut written = Instant::now();
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Some(Ok(event)) => write(index).apply(&event),
+                Some(Err(error)) => {
+                    if options.log {
+                        warn!("{}", error);
+                    }
+                }
+                None => break,
+            },
+            _ = ticker.tick() => {
+                if read(index).rescan {
+                    if options.log {
+                        info!("Ignore files changed or events were lost, scanning directories...");
+                    }
+                    match scan(root, options).await {
+                        Ok(scanned) => write(index).reset(scanned.entries),
+                        Err(error) => warn!("Could not scan `{}`, retrying: {}", root.display(), error),
+                    }
+                }
+
+                if read(index).dirty || written.elapsed() >= KEEP_ALIVE {
+                    let data: Data = read(index).to_data(fingerprint);
+                    cache.write(&data)?;
+                    write(index).dirty = false;
+                    written = Instant::now();
+
+                    if options.log {
+                        info!("Wrote {} entries into `{}`", data.size, cache.location().display());
+                    }
+                }
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    if read(index).dirty {
+        cache.write(&read(index).to_data(fingerprint))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seek::Exclusions;
+    use tempfile::tempdir;
+
+    #[test]
+    fn t_index_add_remove() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("kept")).unwrap();
+        let options = ScanOptions {
+            depth: usize::MAX,
+            log: false,
+            no_ignore: true,
+            threads: 1,
+            exclusions: Exclusions::new(&["skipped".to_string()], &[]).unwrap(),
+        };
+        let mut index = Index::new(&root, &options, vec![Entry::from_path(&root.join("kept")).unwrap()]);
+
+        // a directory moved in along with its content
+        let moved = root.join("moved");
+        fs::create_dir_all(moved.join("inner")).unwrap();
+        fs::create_dir_all(moved.join("skipped")).unwrap();
+        fs::write(moved.join("inner").join("a.txt"), "").unwrap();
+        index.add(&moved);
+        assert!(index.dirty);
+        assert!(index.entries.contains_key(&moved.join("inner").join("a.txt")));
+        assert!(!index.entries.contains_key(&moved.join("skipped")));
+        assert_eq!(index.len(), 4);
+
+        fs::remove_dir_all(&moved).unwrap();
+        index.add(&moved);
+        assert_eq!(index.len(), 1);
+    }
+}